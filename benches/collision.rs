@@ -0,0 +1,61 @@
+#![feature(test)]
+
+extern crate test;
+
+use std::path::Path;
+
+use bevy::prelude::*;
+use test::{black_box, Bencher};
+
+use bevy_starter_project::state::{CollisionGrid, Hitbox, Map, Tile};
+
+// the ground hitboxes level_0 spawns, indexed by entity id, and the first player's hitbox at its spawn point
+fn level() -> (Vec<(Hitbox, Vec3)>, Hitbox, Vec3) {
+    let map = Map::parse(Path::new("level_0.level"), include_str!("../assets/levels/level_0.level")).unwrap();
+    let tiles: Vec<_> = map.tile_info_iter().filter_map(|(_, tile_info)| tile_info).collect();
+    let ground = tiles.iter()
+        .filter(|tile_info| tile_info.tile_type == Tile::Ground)
+        .filter_map(|tile_info| Some((tile_info.hitbox.clone()?, tile_info.position)))
+        .collect();
+    let player = tiles.into_iter().find(|tile_info| tile_info.tile_type == Tile::Player(0)).unwrap();
+    (ground, player.hitbox.unwrap(), player.position)
+}
+
+fn grid(ground: &[(Hitbox, Vec3)]) -> CollisionGrid {
+    let mut grid = CollisionGrid::default();
+    for (index, (hitbox, position)) in ground.iter().enumerate() {
+        let (min, max) = hitbox.bounds(position);
+        grid.insert(Entity::from_raw(index as u32), min, max);
+    }
+    grid
+}
+
+// what player_ground_collision did before the grid: collide with every ground hitbox in the level
+fn every_hitbox(ground: &[(Hitbox, Vec3)], player: &Hitbox, position: &Vec3) -> usize {
+    ground.iter()
+        .filter_map(|(hitbox, ground_position)| player.collide(position, hitbox, ground_position))
+        .count()
+}
+
+// only collide with the ground hitboxes in the cells the player is in
+fn grid_cells(grid: &CollisionGrid, ground: &[(Hitbox, Vec3)], player: &Hitbox, position: &Vec3) -> usize {
+    let (min, max) = player.bounds(position);
+    grid.query(min, max).into_iter()
+        .map(|entity| &ground[entity.id() as usize])
+        .filter_map(|(hitbox, ground_position)| player.collide(position, hitbox, ground_position))
+        .count()
+}
+
+#[bench]
+fn player_ground_collision_every_hitbox(b: &mut Bencher) {
+    let (ground, player, position) = level();
+    b.iter(|| every_hitbox(&ground, &player, black_box(&position)));
+}
+
+#[bench]
+fn player_ground_collision_grid(b: &mut Bencher) {
+    let (ground, player, position) = level();
+    let grid = grid(&ground);
+    assert_eq!(grid_cells(&grid, &ground, &player, &position), every_hitbox(&ground, &player, &position));
+    b.iter(|| grid_cells(&grid, &ground, &player, black_box(&position)));
+}
@@ -0,0 +1,133 @@
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use serde::Deserialize;
+
+use crate::sprite::Pivot;
+
+#[derive(Clone, Copy, Default, PartialEq, Deserialize)]
+pub enum AnimationMode {
+    #[default]
+    Loop,
+    PingPong, // forward then backward, without repeating the first and last frames
+    Once, // stops on the last frame
+}
+
+pub struct AnimationFrame {
+    pub index: usize, // in the texture atlas
+    pub duration: f32, // seconds
+    pub offset: Vec2, // from the pivot
+}
+
+#[derive(TypeUuid)]
+#[uuid = "4e8b1f07-2a6c-4d93-b5e1-7f0c3a9d6b28"]
+pub struct Animation {
+    pub frames: Vec<AnimationFrame>,
+    pub mode: AnimationMode,
+}
+
+// sent every time an animation gets back to its first frame, or reaches its last one when played once
+pub struct AnimationFinished(pub Entity);
+
+#[derive(Component)]
+pub struct Animator {
+    animation: Handle<Animation>,
+    frame: usize,
+    elapsed: f32,
+    forward: bool,
+    finished: bool,
+}
+
+impl Animator {
+    pub fn new(animation: Handle<Animation>) -> Self {
+        Animator {
+            animation,
+            frame: 0,
+            elapsed: 0.0,
+            forward: true,
+            finished: false,
+        }
+    }
+    // moves to the next frame, returns true when that completes the animation
+    fn advance(&mut self, animation: &Animation) -> bool {
+        let last = animation.frames.len() - 1;
+        match animation.mode {
+            AnimationMode::Loop => if self.frame == last {
+                self.frame = 0;
+                true
+            } else {
+                self.frame += 1;
+                false
+            },
+            AnimationMode::Once => if self.frame == last {
+                self.finished = true;
+                true
+            } else {
+                self.frame += 1;
+                false
+            },
+            AnimationMode::PingPong => {
+                if last == 0 {
+                    return true;
+                }
+                if self.frame == last {
+                    self.forward = false;
+                } else if self.frame == 0 {
+                    self.forward = true;
+                }
+                if self.forward {
+                    self.frame += 1;
+                    false
+                } else {
+                    self.frame -= 1;
+                    self.frame == 0
+                }
+            },
+        }
+    }
+}
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<Animation>()
+            .add_event::<AnimationFinished>();
+    }
+}
+
+pub fn animate(
+    time: Res<Time>,
+    animations: Res<Assets<Animation>>,
+    mut events: EventWriter<AnimationFinished>,
+    mut query: Query<(Entity, &mut Animator, &mut TextureAtlasSprite, Option<&mut Pivot>)>,
+) {
+    for (entity, mut animator, mut sprite, pivot) in query.iter_mut() {
+        let animation = match animations.get(&animator.animation) {
+            Some(animation) => animation,
+            None => continue,
+        };
+        if animator.finished {
+            continue;
+        }
+        animator.elapsed += time.delta_seconds();
+        while animator.elapsed >= animation.frames[animator.frame].duration {
+            animator.elapsed -= animation.frames[animator.frame].duration;
+            if animator.advance(animation) {
+                events.send(AnimationFinished(entity));
+                if animator.finished {
+                    break;
+                }
+            }
+        }
+        let frame = &animation.frames[animator.frame];
+        if sprite.index != frame.index {
+            sprite.index = frame.index;
+        }
+        if let Some(mut pivot) = pivot {
+            if pivot.0 != frame.offset {
+                pivot.0 = frame.offset;
+            }
+        }
+    }
+}
@@ -2,3 +2,10 @@ use bevy::prelude::*;
 
 #[derive(Component)]
 pub struct MainCamera;
+
+pub fn reset_camera(mut camera_query: Query<(&MainCamera, &mut Transform, &mut OrthographicProjection)>) {
+    let (_, mut camera_position, mut projection) = camera_query.single_mut();
+    camera_position.translation.x = 0.0;
+    camera_position.translation.y = 0.0;
+    projection.scale = 1.0;
+}
@@ -1,20 +1,251 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use bevy::input::InputSystem;
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Action {
+    Left,
+    Right,
+    Up,
+    Down,
+    Jump,
+    Pause,
+    Interact,
+}
+
+impl Action {
+    pub const ALL: [Action; 7] = [Action::Left, Action::Right, Action::Up, Action::Down, Action::Jump, Action::Pause, Action::Interact];
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum AxisDirection {
+    Negative,
+    Positive,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Binding {
+    Key(KeyCode),
+    Button(GamepadButtonType),
+    Axis(GamepadAxisType, AxisDirection),
+}
 
-#[derive(Component)]
+impl Binding {
+    // how far a stick has to be pushed to count as pressed
+    const AXIS_THRESHOLD: f32 = 0.5;
+    fn is_gamepad(&self) -> bool {
+        !matches!(self, Binding::Key(_))
+    }
+    fn pressed(
+        &self,
+        gamepad: Option<usize>,
+        keys: &Input<KeyCode>,
+        buttons: &Input<GamepadButton>,
+        axes: &Axis<GamepadAxis>,
+        gamepads: &Gamepads,
+    ) -> bool {
+        let mut gamepads = gamepads.iter().filter(|pad| gamepad.map_or(true, |index| pad.0 == index));
+        match *self {
+            Binding::Key(key) => keys.pressed(key),
+            Binding::Button(button) => gamepads.any(|&gamepad| buttons.pressed(GamepadButton(gamepad, button))),
+            Binding::Axis(axis, direction) => gamepads.any(|&gamepad| {
+                let value = axes.get(GamepadAxis(gamepad, axis)).unwrap_or(0.0);
+                match direction {
+                    AxisDirection::Negative => value < -Self::AXIS_THRESHOLD,
+                    AxisDirection::Positive => value > Self::AXIS_THRESHOLD,
+                }
+            }),
+        }
+    }
+    // the first key, button or stick pushed this frame, used when rebinding
+    pub fn just_pressed(
+        keys: &Input<KeyCode>,
+        buttons: &Input<GamepadButton>,
+        axes: &Axis<GamepadAxis>,
+        gamepads: &Gamepads,
+    ) -> Option<Binding> {
+        if let Some(&key) = keys.get_just_pressed().next() {
+            return Some(Binding::Key(key));
+        }
+        if let Some(button) = buttons.get_just_pressed().next() {
+            return Some(Binding::Button(button.1));
+        }
+        let sticks = [
+            GamepadAxisType::LeftStickX,
+            GamepadAxisType::LeftStickY,
+            GamepadAxisType::RightStickX,
+            GamepadAxisType::RightStickY,
+        ];
+        gamepads.iter().flat_map(|&gamepad| sticks.iter().map(move |&axis| (gamepad, axis)))
+            .find_map(|(gamepad, axis)| match axes.get(GamepadAxis(gamepad, axis)).unwrap_or(0.0) {
+                value if value < -Self::AXIS_THRESHOLD => Some(Binding::Axis(axis, AxisDirection::Negative)),
+                value if value > Self::AXIS_THRESHOLD => Some(Binding::Axis(axis, AxisDirection::Positive)),
+                _ => None,
+            })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Binding::Key(key) => write!(f, "{:?}", key),
+            Binding::Button(button) => write!(f, "Pad {:?}", button),
+            Binding::Axis(axis, AxisDirection::Negative) => write!(f, "Pad {:?}-", axis),
+            Binding::Axis(axis, AxisDirection::Positive) => write!(f, "Pad {:?}+", axis),
+        }
+    }
+}
+
+// which player an entity is controlled by, an index into Bindings
+#[derive(Component, Clone, Copy, Default)]
 pub struct Controls {
-    pub left: KeyCode,
-    pub right: KeyCode,
-    pub jump: KeyCode,
-    pub pause: KeyCode,
+    pub player: usize,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ActionMap {
+    bindings: HashMap<Action, Vec<Binding>>,
+    gamepad: Option<usize>, // None reads every gamepad
 }
 
-impl Default for Controls {
+impl ActionMap {
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.bindings.get(&action).map_or(&[], |bindings| &bindings[..])
+    }
+    // replaces the binding of the same kind, so rebinding a key keeps the gamepad binding and vice versa
+    pub fn rebind(&mut self, action: Action, binding: Binding) {
+        let bindings = self.bindings.entry(action).or_default();
+        bindings.retain(|old| old.is_gamepad() != binding.is_gamepad());
+        bindings.push(binding);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bindings {
+    pub players: Vec<ActionMap>,
+}
+
+impl Default for Bindings {
     fn default() -> Self {
-        Controls {
-            left: KeyCode::A,
-            right: KeyCode::D,
-            jump: KeyCode::Space,
-            pause: KeyCode::Return,
+        let player_1 = [
+            (Action::Left, vec![
+                Binding::Key(KeyCode::A),
+                Binding::Button(GamepadButtonType::DPadLeft),
+                Binding::Axis(GamepadAxisType::LeftStickX, AxisDirection::Negative),
+            ]),
+            (Action::Right, vec![
+                Binding::Key(KeyCode::D),
+                Binding::Button(GamepadButtonType::DPadRight),
+                Binding::Axis(GamepadAxisType::LeftStickX, AxisDirection::Positive),
+            ]),
+            (Action::Up, vec![
+                Binding::Key(KeyCode::W),
+                Binding::Button(GamepadButtonType::DPadUp),
+                Binding::Axis(GamepadAxisType::LeftStickY, AxisDirection::Positive),
+            ]),
+            (Action::Down, vec![
+                Binding::Key(KeyCode::S),
+                Binding::Button(GamepadButtonType::DPadDown),
+                Binding::Axis(GamepadAxisType::LeftStickY, AxisDirection::Negative),
+            ]),
+            (Action::Jump, vec![Binding::Key(KeyCode::Space), Binding::Button(GamepadButtonType::South)]),
+            (Action::Pause, vec![Binding::Key(KeyCode::Return), Binding::Button(GamepadButtonType::Start)]),
+            (Action::Interact, vec![Binding::Key(KeyCode::E), Binding::Button(GamepadButtonType::West)]),
+        ];
+        let player_2 = [
+            (Action::Left, vec![
+                Binding::Key(KeyCode::Left),
+                Binding::Button(GamepadButtonType::DPadLeft),
+                Binding::Axis(GamepadAxisType::LeftStickX, AxisDirection::Negative),
+            ]),
+            (Action::Right, vec![
+                Binding::Key(KeyCode::Right),
+                Binding::Button(GamepadButtonType::DPadRight),
+                Binding::Axis(GamepadAxisType::LeftStickX, AxisDirection::Positive),
+            ]),
+            (Action::Up, vec![
+                Binding::Key(KeyCode::Up),
+                Binding::Button(GamepadButtonType::DPadUp),
+                Binding::Axis(GamepadAxisType::LeftStickY, AxisDirection::Positive),
+            ]),
+            (Action::Down, vec![
+                Binding::Key(KeyCode::Down),
+                Binding::Button(GamepadButtonType::DPadDown),
+                Binding::Axis(GamepadAxisType::LeftStickY, AxisDirection::Negative),
+            ]),
+            // not on the arrows, menus navigate with any player's Up and Down and confirm with Jump
+            (Action::Jump, vec![Binding::Key(KeyCode::RControl), Binding::Button(GamepadButtonType::South)]),
+            (Action::Pause, vec![Binding::Key(KeyCode::NumpadEnter), Binding::Button(GamepadButtonType::Start)]),
+            (Action::Interact, vec![Binding::Key(KeyCode::RShift), Binding::Button(GamepadButtonType::West)]),
+        ];
+        Bindings {
+            players: vec![
+                ActionMap { bindings: player_1.into_iter().collect(), gamepad: Some(0) },
+                ActionMap { bindings: player_2.into_iter().collect(), gamepad: Some(1) },
+            ],
         }
     }
 }
+
+#[derive(Default)]
+pub struct ActionState {
+    pressed: HashSet<(usize, Action)>,
+    just_pressed: HashSet<(usize, Action)>,
+    just_released: HashSet<(usize, Action)>,
+}
+
+impl ActionState {
+    pub fn pressed(&self, controls: Controls, action: Action) -> bool {
+        self.pressed.contains(&(controls.player, action))
+    }
+    pub fn just_pressed(&self, controls: Controls, action: Action) -> bool {
+        self.just_pressed.contains(&(controls.player, action))
+    }
+    pub fn just_released(&self, controls: Controls, action: Action) -> bool {
+        self.just_released.contains(&(controls.player, action))
+    }
+    pub fn any_just_pressed(&self, action: Action) -> bool {
+        self.just_pressed.iter().any(|&(_, pressed)| pressed == action)
+    }
+    // makes just_pressed false for the rest of the frame, so the press isn't handled twice
+    pub fn reset(&mut self, action: Action) {
+        self.just_pressed.retain(|&(_, pressed)| pressed != action);
+    }
+    pub fn reset_all(&mut self) {
+        self.just_pressed.clear();
+    }
+}
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ActionSystem;
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ActionState>()
+            .add_system_to_stage(CoreStage::PreUpdate, update_actions.label(ActionSystem).after(InputSystem));
+    }
+}
+
+fn update_actions(
+    bindings: Res<Bindings>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+    mut actions: ResMut<ActionState>,
+) {
+    let pressed: HashSet<(usize, Action)> = bindings.players.iter().enumerate()
+        .flat_map(|(player, map)| Action::ALL.iter()
+            .filter(|&&action| map.bindings(action).iter().any(|binding| binding.pressed(map.gamepad, &keys, &buttons, &axes, &gamepads)))
+            .map(move |&action| (player, action)))
+        .collect();
+    actions.just_pressed = pressed.difference(&actions.pressed).copied().collect();
+    actions.just_released = actions.pressed.difference(&pressed).copied().collect();
+    actions.pressed = pressed;
+}
@@ -0,0 +1,20 @@
+use bevy::asset::Asset;
+use bevy::prelude::*;
+
+// copies an asset into the resource of the same type every time it is (re)loaded, so its file can be tuned while the game runs
+pub fn update_resource<T: Asset + Clone>(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<T>>,
+    assets: Res<Assets<T>>,
+) {
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                if let Some(asset) = assets.get(handle) {
+                    commands.insert_resource(asset.clone());
+                }
+            },
+            AssetEvent::Removed { .. } => {},
+        }
+    }
+}
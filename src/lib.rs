@@ -0,0 +1,22 @@
+#![feature(derive_default_enum)]
+
+pub mod animation;
+
+mod camera;
+
+pub mod controls;
+
+mod hot_reload;
+
+pub mod menu;
+
+pub mod save;
+
+pub mod settings;
+
+pub mod sprite;
+
+pub mod state;
+
+#[macro_use]
+extern crate lazy_static;
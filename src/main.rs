@@ -1,19 +1,12 @@
-#![feature(derive_default_enum)]
-
 use bevy::prelude::*;
 
-mod camera;
-
-mod controls;
-
-mod sprite;
-use sprite::SpriteHandles;
-
-mod state;
-use state::*;
-
-#[macro_use]
-extern crate lazy_static;
+use bevy_starter_project::animation::AnimationPlugin;
+use bevy_starter_project::controls::ControlsPlugin;
+use bevy_starter_project::menu::MenuPlugin;
+use bevy_starter_project::save::SavePlugin;
+use bevy_starter_project::settings::SettingsPlugin;
+use bevy_starter_project::sprite::SpritePlugin;
+use bevy_starter_project::state::*;
 
 mod log;
 use log::*;
@@ -24,12 +17,26 @@ fn main() {
         console_error_panic_hook::set_once();
 
     console_log!("Starting Game!");
-    App::new()
-        .init_resource::<SpriteHandles>()
+    let mut app = App::new();
+    // Assets are reloaded when their file changes, so levels and physics can be tuned while playing
+    #[cfg(not(target_arch = "wasm32"))]
+        app.insert_resource(bevy::asset::AssetServerSettings {
+            watch_for_changes: true,
+            ..Default::default()
+        });
+    app
         .add_plugins(DefaultPlugins)
+        .add_plugin(SpritePlugin)
+        .add_plugin(AnimationPlugin)
+        .add_plugin(SavePlugin)
+        .add_plugin(ControlsPlugin)
+        .add_plugin(MenuPlugin)
+        .add_plugin(SettingsPlugin)
         .add_plugin(Loading)
+        .add_plugin(MainMenu)
         .add_plugin(Game)
         .add_plugin(GameOver)
+        .add_plugin(LevelComplete)
         .add_plugin(Pause)
         .run();
 }
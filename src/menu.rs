@@ -0,0 +1,109 @@
+use bevy::ecs::system::EntityCommands;
+use bevy::prelude::*;
+
+use crate::controls::{Action, ActionState};
+
+// A button that can be selected with Up and Down and pressed with Jump, as well as with the mouse.
+// Only one menu is expected on screen at a time.
+#[derive(Component)]
+pub struct MenuButton(pub usize); // position in the menu, top to bottom
+
+// sent when a menu button is pressed, screens check their own components on the entity to know what to do
+pub struct ButtonPressed(pub Entity);
+
+#[derive(Default)]
+struct MenuSelection(usize);
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MenuSystem;
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<MenuSelection>()
+            .add_event::<ButtonPressed>()
+            .add_system(navigate.label(MenuSystem))
+            .add_system(highlight.after(MenuSystem));
+    }
+}
+
+pub fn button_color(selected: bool) -> UiColor {
+    if selected {
+        Color::DARK_GRAY.into()
+    } else {
+        Color::rgb(0.15, 0.15, 0.15).into()
+    }
+}
+
+pub fn spawn_button<'w, 's, 'a>(parent: &'a mut ChildBuilder<'w, 's, '_>, text: &str, text_style: TextStyle) -> EntityCommands<'w, 's, 'a> {
+    let mut button = parent.spawn_bundle(ButtonBundle {
+        style: Style {
+            size: Size::new(Val::Px(400.0), Val::Px(40.0)),
+            margin: Rect::all(Val::Px(4.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    button.with_children(|parent| {
+        parent.spawn_bundle(TextBundle {
+            text: Text::with_section(text, text_style, Default::default()),
+            ..Default::default()
+        });
+    });
+    button
+}
+
+fn navigate(
+    actions: Res<ActionState>,
+    mut selection: ResMut<MenuSelection>,
+    mut events: EventWriter<ButtonPressed>,
+    added_query: Query<(), Added<MenuButton>>,
+    mouse_query: Query<(Entity, &MenuButton, &Interaction), Changed<Interaction>>,
+    query: Query<(Entity, &MenuButton)>,
+) {
+    let count = query.iter().count();
+    if count == 0 {
+        return;
+    }
+    if added_query.iter().next().is_some() {
+        selection.0 = 0;
+    }
+    for (entity, button, interaction) in mouse_query.iter() {
+        match interaction {
+            Interaction::Hovered => selection.0 = button.0,
+            Interaction::Clicked => {
+                selection.0 = button.0;
+                events.send(ButtonPressed(entity));
+                return;
+            },
+            Interaction::None => {},
+        }
+    }
+    if actions.any_just_pressed(Action::Up) {
+        selection.0 = (selection.0 + count - 1) % count;
+    }
+    if actions.any_just_pressed(Action::Down) {
+        selection.0 = (selection.0 + 1) % count;
+    }
+    if actions.any_just_pressed(Action::Jump) {
+        if let Some((entity, _)) = query.iter().find(|(_, button)| button.0 == selection.0) {
+            events.send(ButtonPressed(entity));
+        }
+    }
+}
+
+fn highlight(
+    selection: Res<MenuSelection>,
+    mut query: Query<(&MenuButton, &mut UiColor)>,
+) {
+    for (button, mut color) in query.iter_mut() {
+        let new_color = button_color(button.0 == selection.0);
+        if color.0 != new_color.0 {
+            *color = new_color;
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::controls::Bindings;
+
+// bumped whenever SaveData changes in a way serde(default) can't cover, with a step added to MIGRATIONS
+const VERSION: u32 = 1;
+
+// MIGRATIONS[n] upgrades a save from version n + 1 to n + 2, so older saves run through every step after theirs
+const MIGRATIONS: [fn(&mut SaveData); (VERSION - 1) as usize] = [];
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct SaveData {
+    version: u32,
+    pub unlocked_levels: usize,
+    pub current_level: usize, // where Continue starts from
+    pub best_times: HashMap<usize, f32>, // seconds, by level index
+    pub collected: HashMap<usize, HashSet<(usize, usize)>>, // tile positions, by level index
+    pub bindings: Bindings,
+    #[serde(skip)]
+    read_only: bool, // the save on disk couldn't be read or backed up, so it isn't overwritten
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        SaveData {
+            version: VERSION,
+            unlocked_levels: 1,
+            current_level: 0,
+            best_times: HashMap::new(),
+            collected: HashMap::new(),
+            bindings: Bindings::default(),
+            read_only: false,
+        }
+    }
+}
+
+// just enough of any version to know how to read the rest
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+impl SaveData {
+    pub fn is_unlocked(&self, level: usize) -> bool {
+        level < self.unlocked_levels
+    }
+    // next is the level after it, 0 when the last one was beaten
+    pub fn complete_level(&mut self, level: usize, next: usize, time: f32) {
+        self.unlocked_levels = self.unlocked_levels.max(level + 2);
+        self.current_level = next;
+        let best = self.best_times.entry(level).or_insert(time);
+        *best = best.min(time);
+    }
+    pub fn is_collected(&self, level: usize, position: (usize, usize)) -> bool {
+        self.collected.get(&level).map_or(false, |collected| collected.contains(&position))
+    }
+    pub fn collect(&mut self, level: usize, position: (usize, usize)) {
+        self.collected.entry(level).or_default().insert(position);
+    }
+    pub fn load() -> Self {
+        let text = match storage::read() {
+            Some(text) => text,
+            None => return SaveData::default(),
+        };
+        let data = ron::from_str::<SaveHeader>(&text)
+            .map_err(|error| error.to_string())
+            .and_then(|header| Self::migrate(header.version, &text));
+        match data {
+            Ok(data) => data,
+            // e.g. a save from a newer version of the game, which is kept rather than overwritten
+            Err(error) => match storage::backup(&text) {
+                Ok(()) => {
+                    warn!("Could not load the save, starting over with the old one backed up: {}", error);
+                    SaveData::default()
+                },
+                Err(backup_error) => {
+                    warn!("Could not load the save ({}) nor back it up ({}), progress won't be saved", error, backup_error);
+                    SaveData { read_only: true, ..Default::default() }
+                },
+            },
+        }
+    }
+    fn migrate(version: u32, text: &str) -> Result<Self, String> {
+        if version == 0 || version > VERSION {
+            return Err(format!("unknown save version {}", version));
+        }
+        // every version so far can be read as the current layout, with serde(default) filling in new fields
+        let mut data: SaveData = ron::from_str(text).map_err(|error| error.to_string())?;
+        data.upgrade(version, &MIGRATIONS);
+        Ok(data)
+    }
+    fn upgrade(&mut self, version: u32, migrations: &[fn(&mut SaveData)]) {
+        for step in &migrations[version as usize - 1..] {
+            step(self);
+        }
+        self.version = migrations.len() as u32 + 1;
+    }
+    fn save(&self) {
+        if self.read_only {
+            return;
+        }
+        let text = ron::ser::to_string_pretty(self, Default::default()).unwrap();
+        if let Err(error) = storage::write(&text) {
+            warn!("Could not save: {}", error);
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+mod storage {
+    use std::path::PathBuf;
+
+    fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join(env!("CARGO_PKG_NAME")).join("save.ron"))
+    }
+
+    pub fn read() -> Option<String> {
+        std::fs::read_to_string(path()?).ok()
+    }
+
+    pub fn write(text: &str) -> Result<(), String> {
+        let path = path().ok_or("no data directory")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|error| error.to_string())?;
+        }
+        std::fs::write(path, text).map_err(|error| error.to_string())
+    }
+
+    // next to the save, replacing any earlier backup
+    pub fn backup(text: &str) -> Result<(), String> {
+        let path = path().ok_or("no data directory")?.with_extension("ron.bak");
+        std::fs::write(path, text).map_err(|error| error.to_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+mod storage {
+    const KEY: &str = concat!(env!("CARGO_PKG_NAME"), ".save");
+
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok()?
+    }
+
+    pub fn read() -> Option<String> {
+        local_storage()?.get_item(KEY).ok()?
+    }
+
+    pub fn write(text: &str) -> Result<(), String> {
+        local_storage().ok_or("no local storage")?
+            .set_item(KEY, text)
+            .map_err(|error| format!("{:?}", error))
+    }
+
+    pub fn backup(text: &str) -> Result<(), String> {
+        local_storage().ok_or("no local storage")?
+            .set_item(concat!(env!("CARGO_PKG_NAME"), ".save.bak"), text)
+            .map_err(|error| format!("{:?}", error))
+    }
+}
+
+pub struct SavePlugin;
+
+impl Plugin for SavePlugin {
+    fn build(&self, app: &mut App) {
+        let data = SaveData::load();
+        app
+            .insert_resource(data.bindings.clone())
+            .insert_resource(data)
+            .add_system_to_stage(CoreStage::Last, write_save);
+    }
+}
+
+// the bindings live in their own resource while playing, and are copied back here when rebound
+fn write_save(
+    bindings: Res<Bindings>,
+    mut data: ResMut<SaveData>,
+) {
+    if bindings.is_changed() && !bindings.is_added() {
+        data.bindings = bindings.clone();
+    }
+    if data.is_changed() && !data.is_added() {
+        data.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn add_level(data: &mut SaveData) {
+        data.unlocked_levels += 1;
+    }
+
+    fn multiply_levels(data: &mut SaveData) {
+        data.unlocked_levels *= 10;
+    }
+
+    #[test]
+    fn migrations_run_in_order_from_the_save_version() {
+        let migrations: [fn(&mut SaveData); 2] = [add_level, multiply_levels];
+        let mut data = SaveData::default();
+        data.upgrade(1, &migrations);
+        assert_eq!((data.version, data.unlocked_levels), (3, 20));
+
+        let mut data = SaveData::default();
+        data.upgrade(2, &migrations);
+        assert_eq!((data.version, data.unlocked_levels), (3, 10));
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        let text = ron::to_string(&SaveData::default()).unwrap();
+        assert!(SaveData::migrate(VERSION, &text).is_ok());
+        assert!(SaveData::migrate(VERSION + 1, &text).is_err());
+    }
+}
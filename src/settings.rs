@@ -0,0 +1,139 @@
+use bevy::prelude::*;
+
+use crate::controls::{Action, ActionState, Binding, Bindings};
+use crate::menu::{spawn_button, ButtonPressed, MenuButton, MenuSystem};
+
+#[derive(Component, Clone, Copy, PartialEq)]
+struct BindingButton {
+    player: usize,
+    action: Action,
+}
+
+// action waiting for a new binding, if any
+#[derive(Default)]
+pub struct Rebinding(Option<BindingButton>);
+
+impl Rebinding {
+    pub fn is_active(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Rebinding>()
+            .add_system(rebind.before(MenuSystem))
+            .add_system(binding_button.after(MenuSystem))
+            .add_system(update_binding_text)
+            .add_system(stop_rebinding);
+    }
+}
+
+// One column of binding buttons per player, returns the first menu position left free
+pub fn spawn_bindings(parent: &mut ChildBuilder, bindings: &Bindings, text_style: &TextStyle) -> usize {
+    parent
+        .spawn_bundle(NodeBundle {
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for player in 0..bindings.players.len() {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::ColumnReverse,
+                            align_items: AlignItems::Center,
+                            margin: Rect::all(Val::Px(8.0)),
+                            ..Default::default()
+                        },
+                        color: Color::NONE.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn_bundle(TextBundle {
+                            text: Text::with_section(format!("Player {}", player + 1), text_style.clone(), Default::default()),
+                            ..Default::default()
+                        });
+                        for (index, action) in Action::ALL.into_iter().enumerate() {
+                            spawn_button(parent, "", text_style.clone())
+                                .insert(MenuButton(player * Action::ALL.len() + index))
+                                .insert(BindingButton { player, action });
+                        }
+                    });
+            }
+        });
+    bindings.players.len() * Action::ALL.len()
+}
+
+fn binding_button(
+    mut rebinding: ResMut<Rebinding>,
+    mut events: EventReader<ButtonPressed>,
+    query: Query<&BindingButton>,
+) {
+    for event in events.iter() {
+        if let Ok(button) = query.get(event.0) {
+            rebinding.0 = Some(*button);
+        }
+    }
+}
+
+fn rebind(
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<Bindings>,
+    mut actions: ResMut<ActionState>,
+    keys: Res<Input<KeyCode>>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+) {
+    let button = match rebinding.0 {
+        Some(button) => button,
+        None => return,
+    };
+    if let Some(binding) = Binding::just_pressed(&keys, &buttons, &axes, &gamepads) {
+        bindings.players[button.player].rebind(button.action, binding);
+        rebinding.0 = None;
+        // so that the new binding doesn't also move around the menu or go back
+        actions.reset_all();
+    }
+}
+
+fn update_binding_text(
+    rebinding: Res<Rebinding>,
+    bindings: Res<Bindings>,
+    button_query: Query<(&BindingButton, &Children)>,
+    mut text_query: Query<&mut Text>,
+) {
+    for (button, children) in button_query.iter() {
+        let value = if rebinding.0 == Some(*button) {
+            format!("{:?}: press a key or button", button.action)
+        } else {
+            let action_bindings: Vec<String> = bindings.players[button.player].bindings(button.action).iter()
+                .map(|binding| binding.to_string())
+                .collect();
+            format!("{:?}: {}", button.action, action_bindings.join(", "))
+        };
+        for &child in children.iter() {
+            if let Ok(mut text) = text_query.get_mut(child) {
+                if text.sections[0].value != value {
+                    text.sections[0].value = value.clone();
+                }
+            }
+        }
+    }
+}
+
+// stops waiting for a binding when its button goes away, e.g. when leaving the menu
+fn stop_rebinding(
+    mut rebinding: ResMut<Rebinding>,
+    query: Query<&BindingButton>,
+) {
+    if let Some(button) = rebinding.0 {
+        if !query.iter().any(|&other| other == button) {
+            rebinding.0 = None;
+        }
+    }
+}
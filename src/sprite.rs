@@ -1,14 +1,31 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::str::FromStr;
 
+use bevy::asset::{AssetLoader, HandleId, LoadContext, LoadedAsset};
 use bevy::prelude::*;
-use bevy::asset::HandleId;
-use crate::sprite::SpriteVariant::Sprite;
+use bevy::reflect::TypeUuid;
+use bevy::transform::TransformSystem;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+use crate::animation::{Animation, AnimationFrame, AnimationMode, Animator};
+
+const SPRITE_MANIFEST: &str = "default.sprites.ron";
+
+struct SpriteSheet {
+    texture_atlas: Handle<TextureAtlas>,
+    animation: Handle<Animation>,
+    indices: HashMap<SpriteTypeStates, usize>, // of each frame in the atlas
+    offsets: HashMap<SpriteTypeStates, Vec2>, // of each frame from its pivot
+    first_index: usize,
+    first_offset: Vec2,
+}
 
 #[derive(Default)]
 pub struct SpriteHandles {
-    pub handles: HashMap<String, Vec<Handle<Image>>>,
+    pub handles: HashMap<SpriteType, Vec<Handle<Image>>>,
+    sheets: HashMap<SpriteType, SpriteSheet>,
+    packed_sheets: HashMap<String, Handle<TextureAtlas>>, // shared by every sprite type cut from them
 }
 
 impl SpriteHandles {
@@ -17,27 +34,92 @@ impl SpriteHandles {
             .map(|(_, handles)| handles.iter().map(|h| h.id))
             .flatten()
     }
-}
-
-#[derive(Component)]
-pub struct SpriteTimer {
-    pub timer: Timer,
-}
-
-impl SpriteTimer {
-    pub fn from_seconds(duration: f32) -> Self {
-        SpriteTimer {
-            timer: Timer::from_seconds(duration, true)
+    // Builds the atlas and animation of a sprite type the first time, once its images are loaded.
+    // Separate images get packed in whatever order fits best, so the animation keeps track of where each one ended up.
+    pub fn build_sheet(
+        &mut self,
+        name: SpriteType,
+        sprites: &SpriteManifest,
+        texture_atlases: &mut Assets<TextureAtlas>,
+        textures: &mut Assets<Image>,
+        animations: &mut Assets<Animation>,
+    ) {
+        if self.sheets.contains_key(&name) {
+            return;
         }
+        let definition = &sprites.sprites[&name];
+        let handles = &self.handles[&name];
+        let (texture_atlas, indices): (Handle<TextureAtlas>, Vec<usize>) = match &definition.sheet {
+            Some(sheet_name) => {
+                let sheet = &sprites.sheets[sheet_name];
+                let texture_atlas = self.packed_sheets.entry(sheet_name.clone())
+                    .or_insert_with(|| texture_atlases.add(TextureAtlas::from_grid(
+                        handles[0].clone(),
+                        Vec2::splat(sheet.tile_size),
+                        sheet.columns,
+                        sheet.rows,
+                    )))
+                    .clone();
+                (texture_atlas, definition.frames.iter().map(|frame| frame.tile.unwrap()).collect())
+            },
+            None => {
+                let mut texture_atlas_builder = TextureAtlasBuilder::default();
+                for handle in handles.iter() {
+                    let texture = textures.get(handle).unwrap();
+                    texture_atlas_builder.add_texture(handle.clone(), texture);
+                }
+                let texture_atlas = texture_atlas_builder.finish(textures).unwrap();
+                let indices = handles.iter().map(|handle| texture_atlas.get_texture_index(handle).unwrap()).collect();
+                (texture_atlases.add(texture_atlas), indices)
+            },
+        };
+        let atlas = texture_atlases.get(&texture_atlas).unwrap();
+        let offsets: Vec<Vec2> = indices.iter().zip(definition.frames.iter())
+            .map(|(&index, frame)| frame.offset(atlas.textures[index].size()))
+            .collect();
+        let animation = Animation {
+            frames: indices.iter().zip(offsets.iter()).zip(definition.frames.iter())
+                .map(|((&index, &offset), frame)| AnimationFrame {
+                    index,
+                    duration: frame.duration,
+                    offset,
+                })
+                .collect(),
+            mode: definition.mode,
+        };
+        self.sheets.insert(name, SpriteSheet {
+            texture_atlas,
+            animation: animations.add(animation),
+            first_index: indices[0],
+            first_offset: offsets[0],
+            indices: definition.frames.iter().map(|frame| frame.state).zip(indices).collect(),
+            offsets: definition.frames.iter().map(|frame| frame.state).zip(offsets).collect(),
+        });
+    }
+    // every entity of a sprite type shares the same atlas and animation, only the animator is their own
+    pub fn sheet(&self, name: SpriteType) -> (Handle<TextureAtlas>, TextureAtlasSprite, Animator, Pivot) {
+        let sheet = &self.sheets[&name];
+        (
+            sheet.texture_atlas.clone(),
+            TextureAtlasSprite::new(sheet.first_index),
+            Animator::new(sheet.animation.clone()),
+            Pivot(sheet.first_offset),
+        )
+    }
+    // a single frame, for sprites that don't animate
+    pub fn frame(&self, name: SpriteType, state: SpriteTypeStates) -> (Handle<TextureAtlas>, TextureAtlasSprite, Pivot) {
+        let sheet = &self.sheets[&name];
+        (sheet.texture_atlas.clone(), TextureAtlasSprite::new(sheet.indices[&state]), Pivot(sheet.offsets[&state]))
     }
 }
 
 #[derive(Clone)]
 pub enum SpriteVariant {
-    Sprite(&'static str),
+    Sprite(SpriteType, SpriteTypeStates),
     SpriteSheet(SpriteType)
 }
-#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Deserialize)]
 pub enum SpriteType {
     BlueBG,
     BrownBG,
@@ -59,36 +141,39 @@ pub enum SpriteType {
     Angel,
     Ground,
     Heart,
+    Flag,
+    Coin,
+    CoinIcon,
+    TorchLight,
 }
 
-impl FromStr for SpriteType {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "BlueBG" => Ok(SpriteType::BlueBG),
-            "BrownBG" => Ok(SpriteType::BrownBG),
-            "WhiteBG" => Ok(SpriteType::WhiteBG),
-            "GreenBG" => Ok(SpriteType::GreenBG),
-            "IdleGreen" => Ok(SpriteType::IdleGreen),
-            "WalkGreen" => Ok(SpriteType::WalkGreen),
-            "JumpGreen" => Ok(SpriteType::JumpGreen),
-            "Blue" => Ok(SpriteType::Blue),
-            "Pink" => Ok(SpriteType::Pink),
-            "Yellow" => Ok(SpriteType::Yellow),
-            "Beige" => Ok(SpriteType::Beige),
-            "SpikeBall" => Ok(SpriteType::SpikeBall),
-            "Fish" => Ok(SpriteType::Fish),
-            "Block" => Ok(SpriteType::Block),
-            "Hedgehog" => Ok(SpriteType::Hedgehog),
-            "BabyJeremy" => Ok(SpriteType::BabyJeremy),
-            "Jeremy" => Ok(SpriteType::Jeremy),
-            "Angel" => Ok(SpriteType::Angel),
-            "Ground" => Ok(SpriteType::Ground),
-            "Heart" => Ok(SpriteType::Heart),
-            _ => Err(()),
-        }
-    }
+impl SpriteType {
+    pub const ALL: [SpriteType; 24] = [
+        SpriteType::BlueBG,
+        SpriteType::BrownBG,
+        SpriteType::WhiteBG,
+        SpriteType::GreenBG,
+        SpriteType::IdleGreen,
+        SpriteType::WalkGreen,
+        SpriteType::JumpGreen,
+        SpriteType::Blue,
+        SpriteType::Pink,
+        SpriteType::Yellow,
+        SpriteType::Beige,
+        SpriteType::SpikeBall,
+        SpriteType::Fish,
+        SpriteType::Block,
+        SpriteType::Hedgehog,
+        SpriteType::BabyJeremy,
+        SpriteType::Jeremy,
+        SpriteType::Angel,
+        SpriteType::Ground,
+        SpriteType::Heart,
+        SpriteType::Flag,
+        SpriteType::Coin,
+        SpriteType::CoinIcon,
+        SpriteType::TorchLight,
+    ];
 }
 
 impl fmt::Display for SpriteType {
@@ -97,7 +182,7 @@ impl fmt::Display for SpriteType {
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Deserialize)]
 pub enum SpriteTypeStates {
     Empty,
     Half,
@@ -131,136 +216,329 @@ pub enum SpriteTypeStates {
     AboveRightEmpty,
 }
 
-lazy_static!{
-//TODO: maybe use enums instead of strings lol
-pub static ref SPRITES: HashMap<SpriteType, HashMap<SpriteTypeStates, &'static str>> = HashMap::from([
-    (SpriteType::BlueBG, HashMap::from([
-        (SpriteTypeStates::Empty, "pixel-platformer/Background/background_0000.png"),
-        (SpriteTypeStates::Half, "pixel-platformer/Background/background_0001.png"),
-        (SpriteTypeStates::Full, "pixel-platformer/Background/background_0002.png"),
-    ])),
-    (SpriteType::BrownBG, HashMap::from([
-        (SpriteTypeStates::Empty, "pixel-platformer/Background/background_0003.png"),
-        (SpriteTypeStates::Half, "pixel-platformer/Background/background_0004.png"),
-        (SpriteTypeStates::Full, "pixel-platformer/Background/background_0005.png"),
-    ])),
-    (SpriteType::WhiteBG, HashMap::from([
-        (SpriteTypeStates::Empty, "pixel-platformer/Background/background_0006.png"),
-        (SpriteTypeStates::Half, "pixel-platformer/Background/background_0007.png"),
-        (SpriteTypeStates::Full, "pixel-platformer/Background/background_0008.png"),
-    ])),
-    (SpriteType::GreenBG, HashMap::from([
-        (SpriteTypeStates::Empty, "pixel-platformer/Background/background_0009.png"),
-        (SpriteTypeStates::Half, "pixel-platformer/Background/background_0010.png"),
-        (SpriteTypeStates::Full, "pixel-platformer/Background/background_0011.png"),
-    ])),
-    (SpriteType::IdleGreen, HashMap::from([
-        (SpriteTypeStates::Closed, "pixel-platformer/Characters/character_0000.png"),
-    ])),
-    (SpriteType::WalkGreen, HashMap::from([
-        (SpriteTypeStates::Closed, "pixel-platformer/Characters/character_0000.png"),
-        (SpriteTypeStates::Open, "pixel-platformer/Characters/character_0001.png"),
-    ])),
-    (SpriteType::JumpGreen, HashMap::from([
-        (SpriteTypeStates::Open, "pixel-platformer/Characters/character_0001.png"),
-    ])),
-    (SpriteType::Blue, HashMap::from([
-        (SpriteTypeStates::Closed, "pixel-platformer/Characters/character_0002.png"),
-        (SpriteTypeStates::Open, "pixel-platformer/Characters/character_0003.png"),
-    ])),
-    (SpriteType::Pink, HashMap::from([
-        (SpriteTypeStates::Closed, "pixel-platformer/Characters/character_0004.png"),
-        (SpriteTypeStates::Open, "pixel-platformer/Characters/character_0005.png"),
-    ])),
-    (SpriteType::Yellow, HashMap::from([
-        (SpriteTypeStates::Closed, "pixel-platformer/Characters/character_0006.png"),
-        (SpriteTypeStates::Open, "pixel-platformer/Characters/character_0007.png"),
-    ])),
-    (SpriteType::SpikeBall, HashMap::from([
-        (SpriteTypeStates::Idle, "pixel-platformer/Characters/character_0008.png"),
-    ])),
-    (SpriteType::Beige, HashMap::from([
-        (SpriteTypeStates::Closed, "pixel-platformer/Characters/character_0009.png"),
-        (SpriteTypeStates::Open, "pixel-platformer/Characters/character_0010.png"),
-    ])),
-    (SpriteType::Fish, HashMap::from([
-        (SpriteTypeStates::Closed, "pixel-platformer/Characters/character_0011.png"),
-        (SpriteTypeStates::Open, "pixel-platformer/Characters/character_0012.png"),
-    ])),
-    (SpriteType::Block, HashMap::from([
-        (SpriteTypeStates::Surprised, "pixel-platformer/Characters/character_0013.png"),
-        (SpriteTypeStates::Pissed, "pixel-platformer/Characters/character_0014.png"),
-    ])),
-    (SpriteType::Hedgehog, HashMap::from([
-        (SpriteTypeStates::Idle0, "pixel-platformer/Characters/character_0015.png"),
-        (SpriteTypeStates::Idle1, "pixel-platformer/Characters/character_0016.png"),
-        (SpriteTypeStates::Idle2, "pixel-platformer/Characters/character_0017.png"),
-    ])),
-    (SpriteType::BabyJeremy, HashMap::from([
-        (SpriteTypeStates::Idle0, "pixel-platformer/Characters/character_0018.png"),
-        (SpriteTypeStates::Idle1, "pixel-platformer/Characters/character_0019.png"),
-        (SpriteTypeStates::Idle0, "pixel-platformer/Characters/character_0020.png"),
-    ])),
-    (SpriteType::Jeremy, HashMap::from([
-        (SpriteTypeStates::Idle0, "pixel-platformer/Characters/character_0021.png"),
-        (SpriteTypeStates::Idle1, "pixel-platformer/Characters/character_0022.png"),
-        (SpriteTypeStates::Idle2, "pixel-platformer/Characters/character_0023.png"),
-    ])),
-    (SpriteType::Angel, HashMap::from([
-        (SpriteTypeStates::Idle0, "pixel-platformer/Characters/character_0024.png"),
-        (SpriteTypeStates::Idle1, "pixel-platformer/Characters/character_0025.png"),
-        (SpriteTypeStates::Idle2, "pixel-platformer/Characters/character_0026.png"),
-    ])),
-    (SpriteType::Ground, HashMap::from([
-        (SpriteTypeStates::AloneGrass, "pixel-platformer/Tiles/tile_0000.png"),
-        (SpriteTypeStates::RightGrass, "pixel-platformer/Tiles/tile_0001.png"),
-        (SpriteTypeStates::LeftRightGrass, "pixel-platformer/Tiles/tile_0002.png"),
-        (SpriteTypeStates::LeftGrass, "pixel-platformer/Tiles/tile_0003.png"),
-        (SpriteTypeStates::DownGrass, "pixel-platformer/Tiles/tile_0020.png"),
-        (SpriteTypeStates::DownGrassRight, "pixel-platformer/Tiles/tile_0021.png"),
-        (SpriteTypeStates::DownGrassLeftRight, "pixel-platformer/Tiles/tile_0022.png"),
-        (SpriteTypeStates::DownGrassLeft, "pixel-platformer/Tiles/tile_0023.png"),
-        (SpriteTypeStates::Full, "pixel-platformer/Tiles/tile_0122.png"),
-        (SpriteTypeStates::Above, "pixel-platformer/Tiles/tile_0140.png"),
-        (SpriteTypeStates::LeftAbove, "pixel-platformer/Tiles/tile_0143.png"),
-        (SpriteTypeStates::RightAbove, "pixel-platformer/Tiles/tile_0141.png"),
-        (SpriteTypeStates::BelowAbove, "pixel-platformer/Tiles/tile_0120.png"),
-        (SpriteTypeStates::LeftEmpty, "pixel-platformer/Tiles/tile_0121.png"),
-        (SpriteTypeStates::RightEmpty, "pixel-platformer/Tiles/tile_0123.png"),
-        (SpriteTypeStates::BelowEmpty, "pixel-platformer/Tiles/tile_0142.png"),
-        (SpriteTypeStates::BelowLeftEmpty, "pixel-platformer/Tiles/tile_0005.png"),
-        (SpriteTypeStates::BelowRightEmpty, "pixel-platformer/Tiles/tile_0004.png"),
-        (SpriteTypeStates::AboveLeftEmpty, "pixel-platformer/Tiles/tile_0025.png"),
-        (SpriteTypeStates::AboveRightEmpty, "pixel-platformer/Tiles/tile_0024.png"),
-    ])),
-    (SpriteType::Heart, HashMap::from([
-        (SpriteTypeStates::Full, "pixel-platformer/Tiles/tile_0044.png"),
-        (SpriteTypeStates::Half, "pixel-platformer/Tiles/tile_0045.png"),
-        (SpriteTypeStates::Empty, "pixel-platformer/Tiles/tile_0046.png"),
-    ])),
-    ("torch light", HashMap::from([
-        ("effect", "torch-light-effect.png"),
-    ])),
-]);
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Frame {
+    pub state: SpriteTypeStates,
+    #[serde(default)]
+    pub path: Option<String>, // a whole image
+    #[serde(default)]
+    pub tile: Option<usize>, // or a tile of the sprite's packed sheet
+    #[serde(default = "Frame::default_duration")]
+    pub duration: f32, // seconds
+    #[serde(default = "Frame::default_pivot")]
+    pub pivot: (f32, f32), // fraction of the frame from its bottom left corner
+}
+
+impl Frame {
+    fn default_duration() -> f32 {
+        0.2
+    }
+    fn default_pivot() -> (f32, f32) {
+        (0.5, 0.5)
+    }
+    // from the pivot to the center of the frame, which is where bevy draws it
+    fn offset(&self, size: Vec2) -> Vec2 {
+        (Vec2::splat(0.5) - Vec2::from(self.pivot)) * size
+    }
+}
+
+// offset of the frame being shown from its pivot, kept up to date by animate
+#[derive(Component, Clone, Copy, Default)]
+pub struct Pivot(pub Vec2);
+
+// Bevy 0.6 draws a sprite around its entity's position, so the offset is added to where it's drawn rather than to the
+// entity, which keeps hitboxes, children and everything else at the pivot. Mirrored with the sprite.
+fn apply_pivot(mut query: Query<(&Pivot, &Transform, &TextureAtlasSprite, &mut GlobalTransform), Without<Parent>>) {
+    for (pivot, transform, sprite, mut global_transform) in query.iter_mut() {
+        let offset = if sprite.flip_x { Vec2::new(-pivot.0.x, pivot.0.y) } else { pivot.0 };
+        *global_transform = GlobalTransform::from(*transform);
+        global_transform.translation += (offset * transform.scale.truncate()).extend(0.0);
+    }
+}
+
+// an image made of equally sized tiles, counted left to right, top to bottom
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PackedSheet {
+    pub name: String,
+    pub path: String,
+    pub tile_size: f32,
+    pub columns: usize,
+    pub rows: usize,
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SpriteDefinition {
+    pub sprite: SpriteType,
+    #[serde(default)]
+    pub sheet: Option<String>, // name of the packed sheet the frame tiles are in
+    pub frames: Vec<Frame>, // in animation order
+    #[serde(default)]
+    pub mode: AnimationMode,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SpriteManifestFile {
+    sheets: Vec<PackedSheet>,
+    sprites: Vec<SpriteDefinition>,
+}
+
+#[derive(Clone, Default, TypeUuid)]
+#[uuid = "9c2e6a41-3f7d-4d0b-8a5e-1b6c7d2f4e93"]
+pub struct SpriteManifest {
+    sheets: HashMap<String, PackedSheet>,
+    sprites: HashMap<SpriteType, SpriteDefinition>,
+}
+
+impl SpriteManifest {
+    pub fn parse(bytes: &[u8]) -> Result<Self, SpriteManifestError> {
+        let file: SpriteManifestFile = ron::de::from_bytes(bytes).map_err(SpriteManifestError::Parse)?;
+        let mut sheets = HashMap::new();
+        for sheet in file.sheets {
+            let name = sheet.name.clone();
+            if sheets.insert(sheet.name.clone(), sheet).is_some() {
+                return Err(SpriteManifestError::DuplicateSheet(name));
+            }
+        }
+        let mut sprites = HashMap::new();
+        for definition in file.sprites {
+            let sprite = definition.sprite;
+            if definition.frames.is_empty() {
+                return Err(SpriteManifestError::NoFrames(sprite));
+            }
+            let sheet = match &definition.sheet {
+                Some(name) => Some(sheets.get(name).ok_or_else(|| SpriteManifestError::UnknownSheet(sprite, name.clone()))?),
+                None => None,
+            };
+            let mut states = HashSet::new();
+            for frame in definition.frames.iter() {
+                if frame.duration <= 0.0 {
+                    return Err(SpriteManifestError::InvalidDuration(sprite, frame.state));
+                }
+                if !states.insert(frame.state) {
+                    return Err(SpriteManifestError::DuplicateState(sprite, frame.state));
+                }
+                // a sprite is either cut from one sheet or made of separate images, so they can share an atlas
+                match (sheet, frame.tile, &frame.path) {
+                    (Some(sheet), Some(tile), None) => if tile >= sheet.columns * sheet.rows {
+                        return Err(SpriteManifestError::TileOutOfRange(sprite, frame.state, tile));
+                    },
+                    (None, None, Some(_)) => {},
+                    _ => return Err(SpriteManifestError::InvalidFrame(sprite, frame.state)),
+                }
+            }
+            if sprites.insert(sprite, definition).is_some() {
+                return Err(SpriteManifestError::DuplicateSprite(sprite));
+            }
+        }
+        if let Some(&sprite) = SpriteType::ALL.iter().find(|sprite| !sprites.contains_key(sprite)) {
+            return Err(SpriteManifestError::MissingSprite(sprite));
+        }
+        Ok(SpriteManifest { sheets, sprites })
+    }
+    pub fn is_loaded(&self) -> bool {
+        !self.sprites.is_empty()
+    }
+    // the files a sprite type needs, its sheet or one per frame
+    pub fn images(&self, sprite: SpriteType) -> Vec<&str> {
+        let definition = &self.sprites[&sprite];
+        match &definition.sheet {
+            Some(sheet) => vec![&self.sheets[sheet].path[..]],
+            None => definition.frames.iter().filter_map(|frame| frame.path.as_deref()).collect(),
+        }
+    }
+    // only for frames that are a whole image
+    pub fn path(&self, sprite: SpriteType, state: SpriteTypeStates) -> &str {
+        self.sprites[&sprite].frames.iter()
+            .find(|frame| frame.state == state)
+            .and_then(|frame| frame.path.as_deref())
+            .unwrap_or_else(|| panic!("{} has no {:?} image", sprite, state))
+    }
+    fn paths(&self) -> impl Iterator<Item = &str> + '_ {
+        self.sheets.values().map(|sheet| &sheet.path[..])
+            .chain(self.sprites.values().flat_map(|definition| definition.frames.iter().filter_map(|frame| frame.path.as_deref())))
+    }
+}
+
+#[derive(Debug)]
+pub enum SpriteManifestError {
+    Parse(ron::Error),
+    DuplicateSheet(String),
+    DuplicateSprite(SpriteType),
+    DuplicateState(SpriteType, SpriteTypeStates),
+    NoFrames(SpriteType),
+    UnknownSheet(SpriteType, String),
+    InvalidFrame(SpriteType, SpriteTypeStates),
+    TileOutOfRange(SpriteType, SpriteTypeStates, usize),
+    InvalidDuration(SpriteType, SpriteTypeStates),
+    MissingSprite(SpriteType),
+    MissingFile(String),
+}
+
+impl fmt::Display for SpriteManifestError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: ", SPRITE_MANIFEST)?;
+        match self {
+            SpriteManifestError::Parse(error) => write!(f, "{}", error),
+            SpriteManifestError::DuplicateSheet(name) => write!(f, "sheet '{}' is defined more than once", name),
+            SpriteManifestError::DuplicateSprite(sprite) => write!(f, "{} is defined more than once", sprite),
+            SpriteManifestError::DuplicateState(sprite, state) => write!(f, "{} has more than one {:?} frame", sprite, state),
+            SpriteManifestError::NoFrames(sprite) => write!(f, "{} has no frames", sprite),
+            SpriteManifestError::UnknownSheet(sprite, name) => write!(f, "{} uses sheet '{}' which isn't defined", sprite, name),
+            SpriteManifestError::InvalidFrame(sprite, state) => write!(f, "{} {:?} frame needs a tile if the sprite has a sheet and a path otherwise", sprite, state),
+            SpriteManifestError::TileOutOfRange(sprite, state, tile) => write!(f, "{} {:?} frame uses tile {} which is outside its sheet", sprite, state, tile),
+            SpriteManifestError::InvalidDuration(sprite, state) => write!(f, "{} {:?} frame has to last longer than 0 seconds", sprite, state),
+            SpriteManifestError::MissingSprite(sprite) => write!(f, "{} is not defined", sprite),
+            SpriteManifestError::MissingFile(path) => write!(f, "'{}' doesn't exist", path),
+        }
+    }
+}
+
+impl std::error::Error for SpriteManifestError {}
+
+#[derive(Default)]
+pub struct SpriteManifestHandle(Handle<SpriteManifest>);
+
+impl SpriteManifestHandle {
+    pub fn load(&mut self, asset_server: &AssetServer) {
+        self.0 = asset_server.load(SPRITE_MANIFEST);
+    }
+    pub fn id(&self) -> HandleId {
+        self.0.id
+    }
+}
+
+#[derive(Default)]
+pub struct SpriteManifestLoader;
+
+impl AssetLoader for SpriteManifestLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let manifest = SpriteManifest::parse(bytes)?;
+            for path in manifest.paths() {
+                if load_context.read_asset_bytes(path).await.is_err() {
+                    return Err(SpriteManifestError::MissingFile(path.to_string()).into());
+                }
+            }
+            load_context.set_default_asset(LoadedAsset::new(manifest));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["sprites.ron"]
+    }
+}
+
+pub struct SpritePlugin;
+
+impl Plugin for SpritePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_asset::<SpriteManifest>()
+            .init_asset_loader::<SpriteManifestLoader>()
+            .init_resource::<SpriteManifest>()
+            .init_resource::<SpriteManifestHandle>()
+            .init_resource::<SpriteHandles>()
+            .add_system(update_sprite_manifest)
+            .add_system_to_stage(CoreStage::PostUpdate, apply_pivot.after(TransformSystem::TransformPropagate));
+    }
+}
+
+// Unlike other assets the manifest isn't reloaded while the game runs, the sheets built from it and the sprites
+// already spawned would no longer match it
+fn update_sprite_manifest(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<SpriteManifest>>,
+    manifests: Res<Assets<SpriteManifest>>,
+) {
+    for event in events.iter() {
+        match event {
+            AssetEvent::Created { handle } => {
+                if let Some(manifest) = manifests.get(handle) {
+                    commands.insert_resource(manifest.clone());
+                }
+            },
+            AssetEvent::Modified { .. } => warn!("The sprite manifest changed, restart the game to use it"),
+            AssetEvent::Removed { .. } => {},
+        }
+    }
 }
 
-pub fn load_sprites(name: SpriteType, asset_server: &Res<AssetServer>) -> Vec<Handle<Image>> {
-    SPRITES[&name].iter().map(
-            |(_, &path)| asset_server.load(path)
+pub fn load_sprites(name: SpriteType, manifest: &SpriteManifest, asset_server: &AssetServer) -> Vec<Handle<Image>> {
+    manifest.images(name).into_iter().map(
+            |path| asset_server.load(path)
         ).collect()
 }
 
-pub fn spawn(
-    name: String,
-    sprite_handles: &Res<SpriteHandles>,
-    texture_atlases: &mut ResMut<Assets<TextureAtlas>>,
-    textures: &mut ResMut<Assets<Image>>,
-) -> Handle<TextureAtlas> {
-    let mut texture_atlas_builder = TextureAtlasBuilder::default();
-    for handle in sprite_handles.handles[&name].iter() {
-        let texture = textures.get(handle).unwrap();
-        texture_atlas_builder.add_texture(handle.clone(), texture);
+#[cfg(test)]
+mod tests {
+    use bevy::asset::AssetPlugin;
+
+    use super::*;
+
+    fn spawn_enemies(
+        mut commands: Commands,
+        sprites: Res<SpriteManifest>,
+        mut sprite_handles: ResMut<SpriteHandles>,
+        mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+        mut textures: ResMut<Assets<Image>>,
+        mut animations: ResMut<Assets<Animation>>,
+    ) {
+        for _ in 0..100 {
+            sprite_handles.build_sheet(SpriteType::Blue, &sprites, &mut texture_atlases, &mut textures, &mut animations);
+            let (texture_atlas, sprite, animator, _) = sprite_handles.sheet(SpriteType::Blue);
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    texture_atlas,
+                    sprite,
+                    ..Default::default()
+                })
+                .insert(animator);
+        }
+    }
+
+    #[test]
+    fn default_manifest_is_valid() {
+        SpriteManifest::parse(include_bytes!("../assets/default.sprites.ron")).unwrap();
+    }
+
+    #[test]
+    fn pivot_offsets_the_frame_center() {
+        let frame = |pivot| Frame { state: SpriteTypeStates::Idle, path: None, tile: Some(0), duration: 0.2, pivot };
+        let size = Vec2::new(24.0, 18.0);
+        assert_eq!(frame(Frame::default_pivot()).offset(size), Vec2::ZERO);
+        assert_eq!(frame((0.0, 0.0)).offset(size), Vec2::new(12.0, 9.0));
+        assert_eq!(frame((0.5, 1.0)).offset(size), Vec2::new(0.0, -9.0));
+    }
+
+    #[test]
+    fn enemies_share_one_atlas() {
+        let mut app = App::new();
+        app
+            .add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin)
+            .add_asset::<Image>()
+            .add_asset::<TextureAtlas>()
+            .add_asset::<Animation>()
+            .insert_resource(SpriteManifest::parse(include_bytes!("../assets/default.sprites.ron")).unwrap())
+            .init_resource::<SpriteHandles>()
+            .add_system(spawn_enemies);
+        let image = app.world.get_resource_mut::<Assets<Image>>().unwrap().add(Image::default());
+        app.world.get_resource_mut::<SpriteHandles>().unwrap().handles.insert(SpriteType::Blue, vec![image]);
+        app.update();
+
+        assert_eq!(app.world.get_resource::<Assets<TextureAtlas>>().unwrap().len(), 1);
+        let mut query = app.world.query::<&Handle<TextureAtlas>>();
+        let atlases: Vec<Handle<TextureAtlas>> = query.iter(&app.world).cloned().collect();
+        assert_eq!(atlases.len(), 100);
+        assert!(atlases.iter().all(|atlas| *atlas == atlases[0]));
     }
-    let texture_atlas = texture_atlas_builder.finish(textures).unwrap();
-    texture_atlases.add(texture_atlas)
 }
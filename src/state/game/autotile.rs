@@ -0,0 +1,200 @@
+use std::ops::BitOr;
+
+use bevy::prelude::*;
+
+use crate::sprite::{SpriteType, SpriteTypeStates};
+
+// Which of the 8 surrounding tiles connect to a tile
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub struct Neighbours(u8);
+
+impl Neighbours {
+    pub const NONE: Neighbours = Neighbours(0);
+    pub const ABOVE: Neighbours = Neighbours(1 << 0);
+    pub const ABOVE_RIGHT: Neighbours = Neighbours(1 << 1);
+    pub const RIGHT: Neighbours = Neighbours(1 << 2);
+    pub const BELOW_RIGHT: Neighbours = Neighbours(1 << 3);
+    pub const BELOW: Neighbours = Neighbours(1 << 4);
+    pub const BELOW_LEFT: Neighbours = Neighbours(1 << 5);
+    pub const LEFT: Neighbours = Neighbours(1 << 6);
+    pub const ABOVE_LEFT: Neighbours = Neighbours(1 << 7);
+    pub const SIDES: Neighbours = Neighbours(Self::ABOVE.0 | Self::RIGHT.0 | Self::BELOW.0 | Self::LEFT.0);
+    pub const ALL: Neighbours = Neighbours(u8::MAX);
+    // offset to each neighbour, in the same order as the bits
+    pub const OFFSETS: [(isize, isize); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+
+    pub fn from_fn(mut connects: impl FnMut(isize, isize) -> bool) -> Self {
+        Self::OFFSETS.iter().enumerate()
+            .filter(|&(_, &(di, dj))| connects(di, dj))
+            .fold(Self::NONE, |neighbours, (bit, _)| neighbours | Neighbours(1 << bit))
+    }
+    pub fn contains(self, other: Neighbours) -> bool {
+        self.0 & other.0 == other.0
+    }
+    pub fn intersects(self, other: Neighbours) -> bool {
+        self.0 & other.0 != 0
+    }
+    // A corner only changes how a tile looks when both sides next to it connect too,
+    // which leaves the 47 cases of a blob tileset out of the 256 combinations
+    pub fn blob(self) -> Self {
+        let corners = [
+            (Self::ABOVE_RIGHT, Self::ABOVE | Self::RIGHT),
+            (Self::BELOW_RIGHT, Self::BELOW | Self::RIGHT),
+            (Self::BELOW_LEFT, Self::BELOW | Self::LEFT),
+            (Self::ABOVE_LEFT, Self::ABOVE | Self::LEFT),
+        ];
+        corners.iter()
+            .filter(|&&(_, sides)| !self.contains(sides))
+            .fold(self, |neighbours, &(corner, _)| Neighbours(neighbours.0 & !corner.0))
+    }
+}
+
+impl BitOr for Neighbours {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        Neighbours(self.0 | other.0)
+    }
+}
+
+// Matches when all of `required` connect and none of `forbidden` do, the rest doesn't matter
+pub struct AutotileRule {
+    pub required: Neighbours,
+    pub forbidden: Neighbours,
+    pub state: SpriteTypeStates,
+}
+
+impl AutotileRule {
+    const fn new(required: Neighbours, forbidden: Neighbours, state: SpriteTypeStates) -> Self {
+        AutotileRule { required, forbidden, state }
+    }
+    fn matches(&self, neighbours: Neighbours) -> bool {
+        neighbours.contains(self.required) && !neighbours.intersects(self.forbidden)
+    }
+}
+
+// Picks the image of a terrain tile from its neighbours. The first matching rule wins, so cases the sprites don't
+// have an image for can fall through to a close enough one. Cases no rule covers use `fallback`.
+pub struct AutotileRules {
+    pub sprite: SpriteType,
+    pub rules: &'static [AutotileRule],
+    pub fallback: SpriteTypeStates,
+}
+
+impl AutotileRules {
+    pub fn state(&self, neighbours: Neighbours) -> SpriteTypeStates {
+        self.find(neighbours).unwrap_or_else(|| {
+            warn!("No {} rule for {:?}, using {:?}", self.sprite, neighbours.blob(), self.fallback);
+            self.fallback
+        })
+    }
+    fn find(&self, neighbours: Neighbours) -> Option<SpriteTypeStates> {
+        let neighbours = neighbours.blob();
+        self.rules.iter()
+            .find(|rule| rule.matches(neighbours))
+            .map(|rule| rule.state)
+    }
+}
+
+const A: Neighbours = Neighbours::ABOVE;
+const R: Neighbours = Neighbours::RIGHT;
+const B: Neighbours = Neighbours::BELOW;
+const L: Neighbours = Neighbours::LEFT;
+const AR: Neighbours = Neighbours::ABOVE_RIGHT;
+const BR: Neighbours = Neighbours::BELOW_RIGHT;
+const BL: Neighbours = Neighbours::BELOW_LEFT;
+const AL: Neighbours = Neighbours::ABOVE_LEFT;
+const fn or(a: Neighbours, b: Neighbours) -> Neighbours {
+    Neighbours(a.0 | b.0)
+}
+
+// The pack has no images for inner corners next to an open side, or for more than one missing corner,
+// so those use the image of the side or of the first missing corner
+pub const GROUND: AutotileRules = AutotileRules {
+    sprite: SpriteType::Ground,
+    rules: &[
+        // grass on top
+        AutotileRule::new(Neighbours::NONE, or(or(A, L), or(R, B)), SpriteTypeStates::AloneGrass),
+        AutotileRule::new(L, or(A, or(R, B)), SpriteTypeStates::LeftGrass),
+        AutotileRule::new(R, or(A, or(L, B)), SpriteTypeStates::RightGrass),
+        AutotileRule::new(or(L, R), or(A, B), SpriteTypeStates::LeftRightGrass),
+        AutotileRule::new(B, or(A, or(L, R)), SpriteTypeStates::DownGrass),
+        AutotileRule::new(or(L, B), or(A, R), SpriteTypeStates::DownGrassLeft),
+        AutotileRule::new(or(R, B), or(A, L), SpriteTypeStates::DownGrassRight),
+        AutotileRule::new(or(or(L, R), B), A, SpriteTypeStates::DownGrassLeftRight),
+        // dirt
+        AutotileRule::new(A, or(L, or(R, B)), SpriteTypeStates::Above),
+        AutotileRule::new(or(A, L), or(R, B), SpriteTypeStates::LeftAbove),
+        AutotileRule::new(or(A, R), or(L, B), SpriteTypeStates::RightAbove),
+        AutotileRule::new(or(A, or(L, R)), B, SpriteTypeStates::BelowEmpty),
+        AutotileRule::new(or(A, B), or(L, R), SpriteTypeStates::BelowAbove),
+        AutotileRule::new(or(A, or(L, B)), R, SpriteTypeStates::RightEmpty),
+        AutotileRule::new(or(A, or(R, B)), L, SpriteTypeStates::LeftEmpty),
+        // surrounded on every side, missing corners
+        AutotileRule::new(Neighbours::SIDES, BL, SpriteTypeStates::BelowLeftEmpty),
+        AutotileRule::new(Neighbours::SIDES, BR, SpriteTypeStates::BelowRightEmpty),
+        AutotileRule::new(Neighbours::SIDES, AL, SpriteTypeStates::AboveLeftEmpty),
+        AutotileRule::new(Neighbours::SIDES, AR, SpriteTypeStates::AboveRightEmpty),
+        AutotileRule::new(Neighbours::ALL, Neighbours::NONE, SpriteTypeStates::Full),
+    ],
+    fallback: SpriteTypeStates::Full,
+};
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn all() -> impl Iterator<Item = Neighbours> {
+        (0..=u8::MAX).map(Neighbours)
+    }
+
+    // the ground images written out per side, independently of the rule table
+    fn expected_ground(neighbours: Neighbours) -> SpriteTypeStates {
+        let has = |side| neighbours.contains(side);
+        match (has(A), has(L), has(R), has(B)) {
+            (false, false, false, false) => SpriteTypeStates::AloneGrass,
+            (false, true, false, false) => SpriteTypeStates::LeftGrass,
+            (false, false, true, false) => SpriteTypeStates::RightGrass,
+            (false, true, true, false) => SpriteTypeStates::LeftRightGrass,
+            (false, false, false, true) => SpriteTypeStates::DownGrass,
+            (false, true, false, true) => SpriteTypeStates::DownGrassLeft,
+            (false, false, true, true) => SpriteTypeStates::DownGrassRight,
+            (false, true, true, true) => SpriteTypeStates::DownGrassLeftRight,
+            (true, false, false, false) => SpriteTypeStates::Above,
+            (true, true, false, false) => SpriteTypeStates::LeftAbove,
+            (true, false, true, false) => SpriteTypeStates::RightAbove,
+            (true, true, true, false) => SpriteTypeStates::BelowEmpty,
+            (true, false, false, true) => SpriteTypeStates::BelowAbove,
+            (true, true, false, true) => SpriteTypeStates::RightEmpty,
+            (true, false, true, true) => SpriteTypeStates::LeftEmpty,
+            (true, true, true, true) if !has(BL) => SpriteTypeStates::BelowLeftEmpty,
+            (true, true, true, true) if !has(BR) => SpriteTypeStates::BelowRightEmpty,
+            (true, true, true, true) if !has(AL) => SpriteTypeStates::AboveLeftEmpty,
+            (true, true, true, true) if !has(AR) => SpriteTypeStates::AboveRightEmpty,
+            (true, true, true, true) => SpriteTypeStates::Full,
+        }
+    }
+
+    #[test]
+    fn blob_keeps_corners_between_connected_sides() {
+        let corners = [(AR, or(A, R)), (BR, or(B, R)), (BL, or(B, L)), (AL, or(A, L))];
+        for neighbours in all() {
+            let blob = neighbours.blob();
+            assert_eq!(blob.blob(), blob);
+            assert_eq!(Neighbours(blob.0 & Neighbours::SIDES.0), Neighbours(neighbours.0 & Neighbours::SIDES.0));
+            for (corner, sides) in corners {
+                let kept = neighbours.contains(corner) && neighbours.contains(sides);
+                assert_eq!(blob.contains(corner), kept, "{:?} -> {:?}", neighbours, blob);
+            }
+        }
+        assert_eq!(all().map(Neighbours::blob).collect::<HashSet<_>>().len(), 47);
+    }
+
+    #[test]
+    fn every_configuration_has_a_ground_rule() {
+        for neighbours in all() {
+            assert_eq!(GROUND.find(neighbours), Some(expected_ground(neighbours.blob())), "{:?}", neighbours);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+
+use crate::save::SaveData;
+
+use super::hitbox::{CollisionEvent, CollisionLayers};
+use super::level::LevelHandles;
+
+// coins collected in the current level
+#[derive(Default)]
+pub struct Score(pub u32);
+
+#[derive(Component)]
+pub struct Coin {
+    pub position: (usize, usize), // tile in the map, how the save remembers it was collected
+}
+
+pub fn reset_score(mut score: ResMut<Score>) {
+    score.0 = 0;
+}
+
+pub fn player_coin_collision(
+    mut commands: Commands,
+    mut score: ResMut<Score>,
+    mut save: ResMut<SaveData>,
+    level_handles: Res<LevelHandles>,
+    mut events: EventReader<CollisionEvent>,
+    coin_query: Query<&Coin>,
+) {
+    // both players can touch the same coin in one step, it only counts once
+    let collected: HashSet<Entity> = events.iter()
+        .filter(|event| event.other_layers.intersects(CollisionLayers::COIN))
+        .map(|event| event.other)
+        .filter(|&coin| coin_query.get(coin).is_ok())
+        .collect();
+    for id in collected {
+        let coin = coin_query.get(id).unwrap();
+        save.collect(level_handles.current, coin.position);
+        commands.entity(id).despawn_recursive();
+        score.0 += 1;
+    }
+}
@@ -24,6 +24,12 @@ impl Direction {
             None
         }
     }
+    pub fn opposite(self) -> Self {
+        match self {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
     pub fn update(&mut self, velocity: &Vec3) {
         if velocity.x > 0.0 {
             *self = Direction::Right;
@@ -0,0 +1,33 @@
+use bevy::prelude::*;
+
+use super::direction::Direction;
+use super::hitbox::{GroundContacts, Hitbox};
+use super::physics::Interpolation;
+use super::velocity::Velocity;
+
+#[derive(Bundle, Default)]
+pub struct EnemyBundle {
+    pub patrol: Patrol,
+    pub hitbox: Hitbox,
+    pub ground_contacts: GroundContacts,
+    pub velocity: Velocity,
+    pub direction: Direction,
+    pub interpolation: Interpolation,
+}
+
+#[derive(Component)]
+pub struct Patrol {
+    pub speed: f32,
+    pub turn_at_ledge: bool,
+    pub turn_at_wall: bool,
+}
+
+impl Default for Patrol {
+    fn default() -> Self {
+        Patrol {
+            speed: 40.0,
+            turn_at_ledge: true,
+            turn_at_wall: true,
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy::prelude::*;
+
+use super::hitbox::Hitbox;
+use super::map::Tile;
+use super::velocity::Velocity;
+
+// Broadphase for hitboxes that never move, bucketed in cells the size of a tile
+#[derive(Default)]
+pub struct CollisionGrid {
+    cells: HashMap<(i32, i32), Vec<Entity>>,
+}
+
+impl CollisionGrid {
+    fn cell(position: Vec2) -> (i32, i32) {
+        let cell = (position / Tile::SIZE).round();
+        (cell.x as i32, cell.y as i32)
+    }
+    fn cells(min: Vec2, max: Vec2) -> impl Iterator<Item = (i32, i32)> {
+        let (min_x, min_y) = Self::cell(min);
+        let (max_x, max_y) = Self::cell(max);
+        (min_x..=max_x).flat_map(move |x| (min_y..=max_y).map(move |y| (x, y)))
+    }
+    pub fn clear(&mut self) {
+        self.cells.clear();
+    }
+    pub fn insert(&mut self, entity: Entity, min: Vec2, max: Vec2) {
+        for cell in Self::cells(min, max) {
+            self.cells.entry(cell).or_default().push(entity);
+        }
+    }
+    pub fn query(&self, min: Vec2, max: Vec2) -> HashSet<Entity> {
+        Self::cells(min, max)
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .copied()
+            .collect()
+    }
+}
+
+pub fn update_collision_grid(
+    mut grid: ResMut<CollisionGrid>,
+    added_query: Query<(), (Added<Hitbox>, Without<Velocity>)>,
+    removed: RemovedComponents<Hitbox>,
+    query: Query<(Entity, &Hitbox, &Transform), Without<Velocity>>,
+) {
+    if added_query.iter().next().is_none() && removed.iter().next().is_none() {
+        return;
+    }
+    grid.clear();
+    for (entity, hitbox, transform) in query.iter() {
+        let (min, max) = hitbox.bounds(&transform.translation);
+        grid.insert(entity, min, max);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a 300x300 tile map with every other column solid
+    fn solids() -> Vec<(Entity, Vec2, Vec2)> {
+        let size = Vec2::splat(Tile::SIZE);
+        (0..300).flat_map(|i| (0..300).map(move |j| (i, j)))
+            .filter(|&(i, _)| i % 2 == 0)
+            .enumerate()
+            .map(|(index, (i, j))| {
+                let center = Vec2::new(i as f32, j as f32) * Tile::SIZE;
+                (Entity::from_raw(index as u32), center - size / 2.0, center + size / 2.0)
+            })
+            .collect()
+    }
+
+    fn grid(solids: &[(Entity, Vec2, Vec2)]) -> CollisionGrid {
+        let mut grid = CollisionGrid::default();
+        for &(entity, min, max) in solids {
+            grid.insert(entity, min, max);
+        }
+        grid
+    }
+
+    fn brute_force(solids: &[(Entity, Vec2, Vec2)], min: Vec2, max: Vec2) -> HashSet<Entity> {
+        solids.iter()
+            .filter(|&&(_, solid_min, solid_max)| min.x < solid_max.x && max.x > solid_min.x && min.y < solid_max.y && max.y > solid_min.y)
+            .map(|&(entity, _, _)| entity)
+            .collect()
+    }
+
+    // roughly the area a character sweeps through in one step, in the middle of the map
+    fn swept_area() -> (Vec2, Vec2) {
+        let min = Vec2::splat(150.0 * Tile::SIZE);
+        (min, min + Vec2::new(24.0, 30.0))
+    }
+
+    #[test]
+    fn grid_finds_every_overlapping_solid() {
+        let solids = solids();
+        let (min, max) = swept_area();
+        assert!(grid(&solids).query(min, max).is_superset(&brute_force(&solids, min, max)));
+    }
+}
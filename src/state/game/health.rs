@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use crate::sprite::SpriteTypeStates;
+
+#[derive(Component)]
+pub struct Health {
+    current: u8, // in half hearts
+    max: u8,
+    invulnerability: Timer,
+}
+
+impl Default for Health {
+    fn default() -> Self {
+        Health::new(6)
+    }
+}
+
+impl Health {
+    const INVULNERABILITY_SECONDS: f32 = 1.5;
+    const BLINK_SECONDS: f32 = 0.1;
+    pub fn new(max: u8) -> Self {
+        let mut invulnerability = Timer::from_seconds(Self::INVULNERABILITY_SECONDS, false);
+        invulnerability.tick(invulnerability.duration());
+        Health {
+            current: max,
+            max,
+            invulnerability,
+        }
+    }
+    pub fn is_dead(&self) -> bool {
+        self.current == 0
+    }
+    pub fn is_invulnerable(&self) -> bool {
+        !self.invulnerability.finished()
+    }
+    pub fn is_blinking(&self) -> bool {
+        self.is_invulnerable() && (self.invulnerability.elapsed_secs() / Self::BLINK_SECONDS) as u32 % 2 == 0
+    }
+    pub fn tick(&mut self, delta: Duration) {
+        self.invulnerability.tick(delta);
+    }
+    pub fn damage(&mut self, half_hearts: u8) -> Result<(), ()> {
+        if self.is_invulnerable() {
+            return Err(());
+        }
+        self.current = self.current.saturating_sub(half_hearts);
+        self.invulnerability.reset();
+        Ok(())
+    }
+    pub fn heart_count(&self) -> usize {
+        (self.max as usize + 1) / 2
+    }
+    pub fn heart(&self, index: usize) -> SpriteTypeStates {
+        match (self.current as usize).saturating_sub(2 * index) {
+            0 => SpriteTypeStates::Empty,
+            1 => SpriteTypeStates::Half,
+            _ => SpriteTypeStates::Full,
+        }
+    }
+}
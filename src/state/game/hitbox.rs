@@ -1,16 +1,44 @@
+use std::ops::BitOr;
+
 use bevy::prelude::*;
 
-//TODO: derive this for all hitboxes for comfort
-trait Collide {
-    fn collide<T: Collide>(&self, other: &T) -> Option<CollisionType>;
+use super::grid::CollisionGrid;
+use super::physics::PHYSICS_STEP;
+use super::velocity::Velocity;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub struct CollisionLayers(u32);
+
+impl CollisionLayers {
+    pub const NONE: CollisionLayers = CollisionLayers(0);
+    pub const GROUND: CollisionLayers = CollisionLayers(1 << 0);
+    pub const PLAYER: CollisionLayers = CollisionLayers(1 << 1);
+    pub const ENEMY: CollisionLayers = CollisionLayers(1 << 2);
+    pub const GOAL: CollisionLayers = CollisionLayers(1 << 3);
+    pub const NPC: CollisionLayers = CollisionLayers(1 << 4);
+    pub const COIN: CollisionLayers = CollisionLayers(1 << 5);
+    pub fn intersects(self, other: CollisionLayers) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl BitOr for CollisionLayers {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        CollisionLayers(self.0 | other.0)
+    }
 }
 
-#[derive(Clone, Default)]
+// `layers` are the layers this hitbox is on, `mask` the layers it wants collision events for
+#[derive(Component, Clone, Default)]
 pub struct Hitbox {
     pub relative_position: Vec3,
     pub size: Vec2,
+    pub layers: CollisionLayers,
+    pub mask: CollisionLayers,
 }
 
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum CollisionType {
     Left,
     Right,
@@ -23,73 +51,214 @@ pub struct Collision {
     pub overlap: f32,
 }
 
+#[derive(Component, Default)]
+pub struct GroundContacts {
+    pub bottom: bool,
+    pub top: bool,
+    pub left: bool,
+    pub right: bool,
+}
+
+// collision_type is from the point of view of `entity`, e.g. Bottom means `other` is below it
+pub struct CollisionEvent {
+    pub entity: Entity,
+    pub other: Entity,
+    pub other_layers: CollisionLayers,
+    pub collision_type: CollisionType,
+    pub overlap: f32,
+}
+
 impl Hitbox {
+    // Which side of this hitbox `other` hits, the one it overlaps the least when it hits two
     pub fn collide(&self, position: &Vec3, other: &Hitbox, other_position: &Vec3) -> Option<Collision> {
-        Hitbox::inner_collide(
-            *other_position + other.relative_position,
-            other.size,
-            *position + self.relative_position,
-            self.size,
-        )
-    }
-
-    //TODO: refactor, since I just copied this and added the overlap
-    pub fn inner_collide(a_pos: Vec3, a_size: Vec2, b_pos: Vec3, b_size: Vec2) -> Option<Collision> {
-        let a_min = a_pos.truncate() - a_size / 2.0;
-        let a_max = a_pos.truncate() + a_size / 2.0;
-
-        let b_min = b_pos.truncate() - b_size / 2.0;
-        let b_max = b_pos.truncate() + b_size / 2.0;
-
-        // check to see if the two rectangles are intersecting
-        if a_min.x < b_max.x && a_max.x > b_min.x && a_min.y < b_max.y && a_max.y > b_min.y {
-            // check to see if we hit on the left or right side
-            let (x_collision, x_depth) = if a_min.x < b_min.x && a_max.x > b_min.x && a_max.x < b_max.x
-            {
-                (Some(CollisionType::Left), b_min.x - a_max.x)
-            } else if a_min.x > b_min.x && a_min.x < b_max.x && a_max.x > b_max.x {
-                (Some(CollisionType::Right), a_min.x - b_max.x)
-            } else {
-                (None, 0.0)
-            };
+        if !self.overlaps(position, other, other_position) {
+            return None;
+        }
+        let (min, max) = self.bounds(position);
+        let (other_min, other_max) = other.bounds(other_position);
+        let x = edge_overlap(other_min.x, other_max.x, min.x, max.x)
+            .map(|(below, overlap)| (if below { CollisionType::Left } else { CollisionType::Right }, overlap));
+        let y = edge_overlap(other_min.y, other_max.y, min.y, max.y)
+            .map(|(below, overlap)| (if below { CollisionType::Bottom } else { CollisionType::Top }, overlap));
+        let (collision_type, overlap) = match (x, y) {
+            (Some(x), Some(y)) => if y.1 < x.1 { y } else { x },
+            (Some(side), None) | (None, Some(side)) => side,
+            (None, None) => return None,
+        };
+        Some(Collision { collision_type, overlap })
+    }
 
-            // check to see if we hit on the top or bottom side
-            let (y_collision, y_depth) = if a_min.y < b_min.y && a_max.y > b_min.y && a_max.y < b_max.y
-            {
-                (Some(CollisionType::Bottom), b_min.y - a_max.y)
-            } else if a_min.y > b_min.y && a_min.y < b_max.y && a_max.y > b_max.y {
-                (Some(CollisionType::Top), a_min.y - b_max.y)
-            } else {
-                (None, 0.0)
-            };
+    pub fn bounds(&self, position: &Vec3) -> (Vec2, Vec2) {
+        let center = (*position + self.relative_position).truncate();
+        (center - self.size / 2.0, center + self.size / 2.0)
+    }
+
+    pub fn overlaps(&self, position: &Vec3, other: &Hitbox, other_position: &Vec3) -> bool {
+        let (min, max) = self.bounds(position);
+        let (other_min, other_max) = other.bounds(other_position);
+        min.x < other_max.x && max.x > other_min.x && min.y < other_max.y && max.y > other_min.y
+    }
+
+    pub fn contains(&self, position: &Vec3, point: Vec2) -> bool {
+        let distance = (point - (*position + self.relative_position).truncate()).abs();
+        distance.x < self.size.x / 2.0 && distance.y < self.size.y / 2.0
+    }
+}
+
+// How far the range a_min..a_max reaches into b_min..b_max across one of its edges, and whether that's the lower edge.
+// None when neither edge is crossed, i.e. one range contains the other.
+fn edge_overlap(a_min: f32, a_max: f32, b_min: f32, b_max: f32) -> Option<(bool, f32)> {
+    if a_min < b_min && a_max > b_min && a_max < b_max {
+        Some((true, a_max - b_min))
+    } else if a_min > b_min && a_min < b_max && a_max > b_max {
+        Some((false, b_max - a_min))
+    } else {
+        None
+    }
+}
+
+pub struct Sweep {
+    pub displacement: Vec2,
+    pub contacts: GroundContacts,
+}
+
+// Moves the box between min and max by displacement, x first and then y, stopping at the first solid in the way.
+// Solids the box already overlaps are ignored, ground_collision pushes the box out of those.
+pub fn sweep(mut min: Vec2, mut max: Vec2, displacement: Vec2, solids: &[(Vec2, Vec2)]) -> Sweep {
+    const EPSILON: f32 = 0.001;
+    let mut contacts = GroundContacts::default();
 
-            // if we had an "x" and a "y" collision, pick the "primary" side using penetration depth
-            match (x_collision, y_collision) {
-                (Some(x_collision), Some(y_collision)) => {
-                    if y_depth.abs() < x_depth.abs() {
-                        Some(Collision{ collision_type: y_collision, overlap: -y_depth })
-                    } else {
-                        Some(Collision{ collision_type: x_collision, overlap: -x_depth })
-                    }
-                }
-                (Some(x_collision), None) => Some(Collision{ collision_type: x_collision, overlap: -x_depth }),
-                (None, Some(y_collision)) => Some(Collision{ collision_type: y_collision, overlap: -y_depth }),
-                (None, None) => None,
+    let mut dx = displacement.x;
+    for &(solid_min, solid_max) in solids {
+        if min.y >= solid_max.y - EPSILON || max.y <= solid_min.y + EPSILON {
+            continue;
+        }
+        if dx > 0.0 && solid_min.x >= max.x - EPSILON && solid_min.x - max.x <= dx {
+            dx = (solid_min.x - max.x).max(0.0);
+            contacts.right = true;
+        } else if dx < 0.0 && solid_max.x <= min.x + EPSILON && solid_max.x - min.x >= dx {
+            dx = (solid_max.x - min.x).min(0.0);
+            contacts.left = true;
+        }
+    }
+    min.x += dx;
+    max.x += dx;
+
+    let mut dy = displacement.y;
+    for &(solid_min, solid_max) in solids {
+        if min.x >= solid_max.x - EPSILON || max.x <= solid_min.x + EPSILON {
+            continue;
+        }
+        if dy > 0.0 && solid_min.y >= max.y - EPSILON && solid_min.y - max.y <= dy {
+            dy = (solid_min.y - max.y).max(0.0);
+            contacts.top = true;
+        } else if dy < 0.0 && solid_max.y <= min.y + EPSILON && solid_max.y - min.y >= dy {
+            dy = (solid_max.y - min.y).min(0.0);
+            contacts.bottom = true;
+        }
+    }
+
+    Sweep {
+        displacement: Vec2::new(dx, dy),
+        contacts,
+    }
+}
+
+pub fn detect_collisions(
+    grid: Res<CollisionGrid>,
+    query: Query<(Entity, &Hitbox, &Transform)>,
+    moving_query: Query<(Entity, &Hitbox, &Transform, &Velocity)>,
+    mut events: EventWriter<CollisionEvent>,
+) {
+    for (entity, hitbox, transform, velocity) in moving_query.iter() {
+        if hitbox.mask == CollisionLayers::NONE {
+            continue;
+        }
+        // static hitboxes come from the grid cells the hitbox swept through this step, moving ones are few enough to check them all
+        let (min, max) = hitbox.bounds(&transform.translation);
+        let displacement = velocity.0.truncate() * PHYSICS_STEP;
+        let static_hitboxes = grid.query(min.min(min - displacement), max.max(max - displacement));
+        let moving_hitboxes = moving_query.iter().map(|(other, _, _, _)| other);
+        for other in static_hitboxes.into_iter().chain(moving_hitboxes) {
+            let (_, other_hitbox, other_transform) = match query.get(other) {
+                Ok(components) => components,
+                Err(_) => continue,
+            };
+            if entity == other || !hitbox.mask.intersects(other_hitbox.layers) {
+                continue;
+            }
+            if let Some(collision) = hitbox.collide(&transform.translation, other_hitbox, &other_transform.translation) {
+                events.send(CollisionEvent {
+                    entity,
+                    other,
+                    other_layers: other_hitbox.layers,
+                    collision_type: collision.collision_type,
+                    overlap: collision.overlap,
+                });
             }
-        } else {
-            None
         }
     }
 }
 
-#[derive(Component, Default)]
-pub struct PlayerGroundHitbox(pub Hitbox);
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-#[derive(Component, Default)]
-pub struct GroundHitbox(pub Hitbox);
+    fn square(x: f32, y: f32, size: f32) -> (Vec2, Vec2) {
+        (Vec2::new(x, y), Vec2::new(x + size, y + size))
+    }
 
-#[derive(Component, Default)]
-pub struct PlayerEnemyHitbox(pub Hitbox);
+    #[test]
+    fn stops_at_the_nearest_solid_past_a_tile() {
+        let (min, max) = square(0.0, 50.0, 10.0);
+        let solids = [square(0.0, -18.0, 18.0), square(0.0, 0.0, 18.0)];
+        let result = sweep(min, max, Vec2::new(0.0, -100.0), &solids);
+        assert_eq!(result.displacement, Vec2::new(0.0, -32.0));
+        assert!(result.contacts.bottom);
 
-#[derive(Component, Default)]
-pub struct EnemyHitbox(pub Hitbox);
+        let (min, max) = square(0.0, 0.0, 10.0);
+        let result = sweep(min, max, Vec2::new(100.0, 0.0), &[square(40.0, 0.0, 18.0)]);
+        assert_eq!(result.displacement, Vec2::new(30.0, 0.0));
+        assert!(result.contacts.right && !result.contacts.bottom);
+    }
+
+    #[test]
+    fn corner_hit_stops_only_the_blocked_axis() {
+        let (min, max) = square(0.0, 0.0, 10.0);
+        let solids = [square(12.0, 12.0, 18.0)];
+        let result = sweep(min, max, Vec2::new(5.0, 5.0), &solids);
+        assert_eq!(result.displacement, Vec2::new(5.0, 2.0));
+        assert!(result.contacts.top);
+        assert!(!result.contacts.right);
+
+        // moving diagonally past the corner without touching it
+        let result = sweep(min, max, Vec2::new(1.0, 5.0), &solids);
+        assert_eq!(result.displacement, Vec2::new(1.0, 5.0));
+        assert!(!result.contacts.top && !result.contacts.right);
+    }
+
+    #[test]
+    fn sliding_along_the_ground_ignores_its_seams() {
+        let ground = [square(0.0, 0.0, 18.0), square(18.0, 0.0, 18.0)];
+        // resting exactly on the ground, and sunk into it or floating above it by less than EPSILON
+        for y in [18.0, 18.0 - 0.0005, 18.0 + 0.0005] {
+            let (min, max) = square(0.0, y, 10.0);
+            let result = sweep(min, max, Vec2::new(20.0, -1.0), &ground);
+            assert_eq!(result.displacement.x, 20.0, "at {}", y);
+            assert!(result.displacement.y.abs() <= 0.001, "at {}", y);
+            assert!(result.contacts.bottom && !result.contacts.right, "at {}", y);
+        }
+    }
+
+    #[test]
+    fn collide_picks_the_shallower_side() {
+        let hitbox = Hitbox { size: Vec2::new(10.0, 10.0), ..Default::default() };
+        let collision = hitbox.collide(&Vec3::ZERO, &hitbox, &Vec3::new(8.0, -9.0, 0.0)).unwrap();
+        assert_eq!(collision.collision_type, CollisionType::Bottom);
+        assert_eq!(collision.overlap, 1.0);
+        let collision = hitbox.collide(&Vec3::ZERO, &hitbox, &Vec3::new(-9.0, 2.0, 0.0)).unwrap();
+        assert_eq!(collision.collision_type, CollisionType::Left);
+        assert_eq!(collision.overlap, 1.0);
+        assert!(hitbox.collide(&Vec3::ZERO, &hitbox, &Vec3::new(10.0, 0.0, 0.0)).is_none());
+    }
+}
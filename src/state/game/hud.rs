@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+
+use crate::controls::Controls;
+use crate::sprite::{SpriteManifest, SpriteType, SpriteTypeStates};
+
+use super::coin::Score;
+use super::health::Health;
+use super::level::{LevelEntity, LevelHandles};
+use super::map::{Map, Tile};
+
+#[derive(Component)]
+pub struct HeartIcon {
+    player: usize,
+    index: usize,
+}
+
+#[derive(Component)]
+pub struct ScoreText;
+
+pub fn spawn_hud(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    sprites: Res<SpriteManifest>,
+    level_handles: Res<LevelHandles>,
+    maps: Res<Assets<Map>>,
+) {
+    let heart_count = Health::default().heart_count();
+    let map = maps.get(level_handles.current()).unwrap();
+    let mut players: Vec<usize> = map.iter()
+        .filter_map(|position| match map[position] {
+            Tile::Player(player) => Some(player),
+            _ => None,
+        })
+        .collect();
+    players.sort_unstable();
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..Default::default()
+                },
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(LevelEntity)
+        .with_children(|parent| {
+            for player in players {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        color: Color::NONE.into(),
+                        ..Default::default()
+                    })
+                    .with_children(|parent| {
+                        for index in 0..heart_count {
+                            parent
+                                .spawn_bundle(ImageBundle {
+                                    style: Style {
+                                        size: Size::new(Val::Px(36.0), Val::Px(36.0)),
+                                        margin: Rect::all(Val::Px(2.0)),
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                })
+                                .insert(HeartIcon { player, index });
+                        }
+                    });
+            }
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        align_items: AlignItems::Center,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(ImageBundle {
+                        style: Style {
+                            size: Size::new(Val::Px(36.0), Val::Px(36.0)),
+                            margin: Rect::all(Val::Px(2.0)),
+                            ..Default::default()
+                        },
+                        image: asset_server.get_handle(sprites.path(SpriteType::CoinIcon, SpriteTypeStates::Idle)).into(),
+                        ..Default::default()
+                    });
+                    parent
+                        .spawn_bundle(TextBundle {
+                            text: Text::with_section(
+                                "0",
+                                TextStyle {
+                                    font: asset_server.load("kenney-fonts/Fonts/Kenney Pixel.ttf"),
+                                    font_size: 40.0,
+                                    color: Color::WHITE,
+                                },
+                                Default::default(),
+                            ),
+                            ..Default::default()
+                        })
+                        .insert(ScoreText);
+                });
+        });
+}
+
+pub fn update_hud(
+    asset_server: Res<AssetServer>,
+    sprites: Res<SpriteManifest>,
+    player_query: Query<(&Controls, &Health)>,
+    mut heart_query: Query<(&HeartIcon, &mut UiImage, &mut Visibility)>,
+) {
+    let healths: HashMap<usize, &Health> = player_query.iter().map(|(controls, health)| (controls.player, health)).collect();
+    for (heart, mut image, mut visibility) in heart_query.iter_mut() {
+        // players who died are despawned, their hearts stay on screen empty
+        let state = match healths.get(&heart.player) {
+            Some(health) => {
+                visibility.is_visible = heart.index < health.heart_count();
+                health.heart(heart.index)
+            },
+            None => SpriteTypeStates::Empty,
+        };
+        let handle = asset_server.get_handle(sprites.path(SpriteType::Heart, state));
+        if image.0 != handle {
+            image.0 = handle;
+        }
+    }
+}
+
+pub fn update_score_text(
+    score: Res<Score>,
+    mut query: Query<&mut Text, With<ScoreText>>,
+) {
+    if !score.is_changed() {
+        return;
+    }
+    for mut text in query.iter_mut() {
+        text.sections[0].value = score.0.to_string();
+    }
+}
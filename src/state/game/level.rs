@@ -0,0 +1,75 @@
+use bevy::asset::{AssetLoader, HandleId, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+
+use super::map::Map;
+
+const LEVELS: [&str; 2] = [
+    "levels/level_0.level",
+    "levels/level_1.level",
+];
+
+#[derive(Component)]
+pub struct LevelEntity;
+
+// seconds spent in the current level, not counting pauses
+#[derive(Default)]
+pub struct LevelTime(pub f32);
+
+pub fn reset_level_time(mut level_time: ResMut<LevelTime>) {
+    level_time.0 = 0.0;
+}
+
+pub fn tick_level_time(time: Res<Time>, mut level_time: ResMut<LevelTime>) {
+    level_time.0 += time.delta_seconds();
+}
+
+#[derive(Default)]
+pub struct LevelHandles {
+    pub handles: Vec<Handle<Map>>,
+    pub current: usize,
+}
+
+impl LevelHandles {
+    pub fn load(&mut self, asset_server: &AssetServer) {
+        self.handles = LEVELS.iter().map(|&path| asset_server.load(path)).collect();
+        self.current = 0;
+    }
+    pub fn current(&self) -> &Handle<Map> {
+        &self.handles[self.current]
+    }
+    pub fn is_last(&self) -> bool {
+        self.current + 1 >= self.handles.len()
+    }
+    pub fn select(&mut self, index: usize) {
+        self.current = index.min(self.handles.len() - 1);
+    }
+    pub fn advance(&mut self) {
+        self.current = (self.current + 1) % self.handles.len();
+    }
+    pub fn id_list(&self) -> impl Iterator<Item = HandleId> + '_ {
+        self.handles.iter().map(|h| h.id)
+    }
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let text = std::str::from_utf8(bytes)?;
+            let map = Map::parse(load_context.path(), text)?;
+            load_context.set_default_asset(LoadedAsset::new(map));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level"]
+    }
+}
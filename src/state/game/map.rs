@@ -1,6 +1,13 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
 use bevy::prelude::{Vec2, Vec3};
+use bevy::reflect::TypeUuid;
 
-use super::hitbox::Hitbox;
+use super::autotile::{Neighbours, GROUND};
+use super::hitbox::{CollisionLayers, Hitbox};
+use super::player::Character;
 
 use crate::sprite::{SpriteType, SpriteTypeStates, SpriteVariant};
 
@@ -8,13 +15,39 @@ use crate::sprite::{SpriteType, SpriteTypeStates, SpriteVariant};
 pub enum Tile {
     Empty,
     Ground,
-    Player,
+    Player(usize),
     Blue,
-    Npc(SpriteType), //TODO: turn into many values to get rid of string
+    Goal,
+    Coin,
+    Npc(SpriteType),
 }
 
 impl Tile {
     pub const SIZE: f32 = 18.0;
+    // Characters are 24 pixels tall with their feet on the bottom row, so their one tile hitbox sits at the bottom of the sprite.
+    // Keeping it one tile lets them fit through one tile gaps.
+    const CHARACTER_HITBOX_OFFSET: f32 = (Tile::SIZE - 24.0) / 2.0;
+    pub fn from_char(c: char) -> Option<Tile> {
+        let tile = match c {
+            '.' => Tile::Empty,
+            '#' => Tile::Ground,
+            '@' => Tile::Player(0),
+            '2' => Tile::Player(1),
+            'B' => Tile::Blue,
+            'G' => Tile::Goal,
+            'c' => Tile::Coin,
+            'p' => Tile::Npc(SpriteType::Pink),
+            'y' => Tile::Npc(SpriteType::Yellow),
+            'e' => Tile::Npc(SpriteType::Beige),
+            'k' => Tile::Npc(SpriteType::Block),
+            'h' => Tile::Npc(SpriteType::Hedgehog),
+            'b' => Tile::Npc(SpriteType::BabyJeremy),
+            'j' => Tile::Npc(SpriteType::Jeremy),
+            'a' => Tile::Npc(SpriteType::Angel),
+            _ => return None,
+        };
+        Some(tile)
+    }
     pub fn connects_to(self, other: Tile) -> bool {
         match (self, other) {
             (Tile::Ground, Tile::Ground) => true,
@@ -30,107 +63,129 @@ pub struct TileInfo {
     pub hitbox: Option<Hitbox>,
 }
 
-type Line = [Tile; Map::HEIGHT];
-
+#[derive(TypeUuid)]
+#[uuid = "5b1bd1e4-5a57-4bc5-a5d6-7c1c8a3b6f20"]
 pub struct Map {
-    values: [Line; Map::WIDTH],
+    width: usize,
+    height: usize,
+    values: Vec<Tile>,
+    origin: Vec2, // world position, in tiles, of the bottom left tile
+    wall_jump: bool,
 }
 
 impl Map {
-    const WIDTH: usize = 100;
-    const HEIGHT: usize = 20;
-    fn left(&self, i: usize, j: usize) -> Tile {
-        if i > 0 { self.values[i-1][j] } else { Tile::Empty }
-    }
-    fn right(&self, i: usize, j: usize) -> Tile {
-        if i+1 < Self::WIDTH { self.values[i+1][j] } else { Tile::Empty }
-    }
-    fn below(&self, i: usize, j: usize) -> Tile {
-        if j > 0 { self.values[i][j-1] } else { Tile::Empty }
+    pub fn get(&self, i: isize, j: isize) -> Tile {
+        if i < 0 || j < 0 || i as usize >= self.width || j as usize >= self.height {
+            Tile::Empty
+        } else {
+            self.values[i as usize * self.height + j as usize]
+        }
     }
-    fn above(&self, i: usize, j: usize) -> Tile {
-        if j+1 < Self::HEIGHT { self.values[i][j+1] } else { Tile::Empty }
+    fn neighbour(&self, i: usize, j: usize, di: isize, dj: isize) -> Tile {
+        self.get(i as isize + di, j as isize + dj)
     }
-    fn below_left(&self, i: usize, j: usize) -> Tile {
-        if i > 0 && j > 0 { self.values[i-1][j-1] } else { Tile::Empty }
+    fn neighbours(&self, i: usize, j: usize) -> Neighbours {
+        let tile = self[(i, j)];
+        Neighbours::from_fn(|di, dj| tile.connects_to(self.neighbour(i, j, di, dj)))
     }
-    fn below_right(&self, i: usize, j: usize) -> Tile {
-        if i+1 < Self::WIDTH && j > 0 { self.values[i+1][j-1] } else { Tile::Empty }
+    pub fn wall_jump(&self) -> bool {
+        self.wall_jump
     }
-    fn above_left(&self, i: usize, j: usize) -> Tile {
-        if i > 0 && j+1 < Self::HEIGHT { self.values[i-1][j+1] } else { Tile::Empty }
+    pub fn spawn_point(&self) -> Option<(usize, usize)> {
+        self.iter().find(|&(i, j)| self[(i, j)] == Tile::Player(0))
     }
-    fn above_right(&self, i: usize, j: usize) -> Tile {
-        if i+1 < Self::WIDTH && j+1 < Self::HEIGHT { self.values[i+1][j+1] } else { Tile::Empty }
+    // A level file is a list of optional "key: value" metadata lines followed by the tiles,
+    // one character per tile, top row first.
+    pub fn parse(path: &Path, text: &str) -> Result<Map, MapError> {
+        let error = |row, column, kind| MapError { path: path.to_path_buf(), row: row + 1, column: column + 1, kind };
+
+        let mut rows: Vec<(usize, &str)> = text.lines().enumerate().collect();
+        let metadata_rows = rows.iter().take_while(|(_, line)| line.contains(':')).count();
+        let tile_rows = rows.split_off(metadata_rows);
+
+        let mut origin = None;
+        let mut wall_jump = false;
+        for (row, line) in rows {
+            let key = line.split_once(':').unwrap().0;
+            let tokens = tokens(line, key.chars().count() + 1);
+            let end = line.chars().count();
+            let invalid = |(column, token)| error(row, column, MapErrorKind::InvalidMetadata(token));
+            match key.trim() {
+                "origin" => {
+                    let coordinates = parse_values::<f32>(&tokens, 2, end).map_err(invalid)?;
+                    origin = Some(Vec2::new(coordinates[0], coordinates[1]));
+                },
+                "wall_jump" => wall_jump = parse_values::<bool>(&tokens, 1, end).map_err(invalid)?[0],
+                name => {
+                    let column = key.chars().take_while(|c| c.is_whitespace()).count();
+                    return Err(error(row, column, MapErrorKind::UnknownMetadata(name.to_string())));
+                },
+            }
+        }
+
+        let (first_row, first_line) = *tile_rows.first().ok_or_else(|| error(metadata_rows, 0, MapErrorKind::Empty))?;
+        let width = first_line.chars().count();
+        let height = tile_rows.len();
+        if width == 0 {
+            return Err(error(first_row, 0, MapErrorKind::Empty));
+        }
+        let mut values = vec![Tile::Empty; width * height];
+        for (j, (row, line)) in tile_rows.iter().rev().enumerate() {
+            let line_width = line.chars().count();
+            if line_width != width {
+                return Err(error(*row, line_width.min(width), MapErrorKind::WrongWidth { width: line_width, expected: width }));
+            }
+            for (i, c) in line.chars().enumerate() {
+                values[i * height + j] = Tile::from_char(c).ok_or_else(|| error(*row, i, MapErrorKind::UnknownTile(c)))?;
+            }
+        }
+
+        let mut map = Map { width, height, values, origin: Vec2::ZERO, wall_jump };
+        map.origin = match (origin, map.spawn_point()) {
+            (Some(origin), _) => origin,
+            (None, Some((i, j))) => -Vec2::new(i as f32, j as f32),
+            (None, None) => Vec2::ZERO,
+        };
+        Ok(map)
     }
     pub fn get_tile_info(&self, i: usize, j: usize) -> Option<TileInfo> {
-        use crate::sprite::SPRITES;
-
         let position = |layer| {
-            let start_point = Vec3::new(-20.0 * Tile::SIZE,-((Self::HEIGHT/2) as f32) * Tile::SIZE, layer);
+            let start_point = (self.origin * Tile::SIZE).extend(layer);
             start_point + Tile::SIZE * Vec3::new(i as f32, j as f32, 0.0)
         };
 
-        let tile = self[i][j];
+        let tile = self[(i, j)];
         match tile {
             Tile::Empty => None,
             Tile::Ground => {
-                let left = tile.connects_to(self.left(i, j));
-                let right = tile.connects_to(self.right(i,j));
-                let below = tile.connects_to(self.below(i, j));
-                let above = tile.connects_to(self.above(i, j));
-                let below_left = tile.connects_to(self.below_left(i, j));
-                let below_right = tile.connects_to(self.below_right(i, j));
-                let above_left = tile.connects_to(self.above_left(i, j));
-                let above_right = tile.connects_to(self.above_right(i, j));
-                let image_key = match (above, left, right, below) {
-                    (false, false, false, false) => SpriteTypeStates::AloneGrass,
-                    (false, true, false, false) => SpriteTypeStates::LeftGrass,
-                    (false, false, true, false) => SpriteTypeStates::RightGrass,
-                    (false, true, true, false) => SpriteTypeStates::LeftRightGrass,
-                    (false, false, false, true) => SpriteTypeStates::DownGrass,
-                    (false, true, false, true) => SpriteTypeStates::DownGrassLeft,
-                    (false, false, true, true) => SpriteTypeStates::DownGrassRight,
-                    (false, true, true, true) => SpriteTypeStates::DownGrassLeftRight,
-                    (true, false, false, false) => SpriteTypeStates::Above,
-                    (true, true, false, false) => SpriteTypeStates::LeftAbove,
-                    (true, false, true, false) => SpriteTypeStates::RightAbove,
-                    (true, true, true, false) => SpriteTypeStates::BelowEmpty,
-                    (true, false, false, true) => SpriteTypeStates::BelowAbove,
-                    (true, true, false, true) => SpriteTypeStates::RightEmpty,
-                    (true, false, true, true) => SpriteTypeStates::LeftEmpty,
-                    (true, true, true, true) => match (below_left, below_right, above_left, above_right) {
-                        //TODO: missing some cases due to not having the images
-                        (false, _, _, _) => SpriteTypeStates::BelowLeftEmpty,
-                        (_, false, _, _) => SpriteTypeStates::BelowRightEmpty,
-                        (_, _, false, _) => SpriteTypeStates::AboveLeftEmpty,
-                        (_, _, _, false) => SpriteTypeStates::AboveRightEmpty,
-                        (_, _, _, _) => SpriteTypeStates::Full,
-                    }
-                };
+                let image_key = GROUND.state(self.neighbours(i, j));
                 let hitbox = if image_key == SpriteTypeStates::Full {
                     None
                 } else {
                     Some(Hitbox {
                         relative_position: Vec3::default(),
                         size: Vec2::new(Tile::SIZE, Tile::SIZE),
+                        layers: CollisionLayers::GROUND,
+                        mask: CollisionLayers::NONE,
                     })
                 };
                 Some(TileInfo {
                     tile_type: tile,
                     position: position(0.5),
-                    image: SpriteVariant::Sprite(SPRITES[&SpriteType::Ground][&image_key]),
+                    image: SpriteVariant::Sprite(GROUND.sprite, image_key),
                     hitbox,
                 })
             },
-            Tile::Player => {
+            Tile::Player(player) => {
                 Some(TileInfo {
                     tile_type: tile,
                     position: position(2.0),
-                    image: SpriteVariant::SpriteSheet(SpriteType::IdleGreen),
+                    image: SpriteVariant::SpriteSheet(Character::for_player(player).idle()),
                     hitbox: Some(Hitbox {
-                        relative_position: Vec3::default(), //TODO: better values
-                        size: Vec2::new(Tile::SIZE, Tile::SIZE), //TODO: better values
+                        relative_position: Vec3::new(0.0, Tile::CHARACTER_HITBOX_OFFSET, 0.0),
+                        size: Vec2::new(Tile::SIZE, Tile::SIZE),
+                        layers: CollisionLayers::PLAYER,
+                        mask: CollisionLayers::GROUND | CollisionLayers::ENEMY | CollisionLayers::GOAL | CollisionLayers::COIN,
                     }),
                 })
             },
@@ -140,8 +195,36 @@ impl Map {
                     position: position(1.0),
                     image: SpriteVariant::SpriteSheet(SpriteType::Blue),
                     hitbox: Some(Hitbox {
-                        relative_position: Vec3::default(), //TODO: better values
-                        size: Vec2::new(Tile::SIZE, Tile::SIZE), //TODO: better values
+                        relative_position: Vec3::new(0.0, Tile::CHARACTER_HITBOX_OFFSET, 0.0),
+                        size: Vec2::new(Tile::SIZE, Tile::SIZE),
+                        layers: CollisionLayers::ENEMY,
+                        mask: CollisionLayers::GROUND,
+                    }),
+                })
+            },
+            Tile::Goal => {
+                Some(TileInfo {
+                    tile_type: tile,
+                    position: position(1.0),
+                    image: SpriteVariant::SpriteSheet(SpriteType::Flag),
+                    hitbox: Some(Hitbox {
+                        relative_position: Vec3::default(),
+                        size: Vec2::new(Tile::SIZE, Tile::SIZE),
+                        layers: CollisionLayers::GOAL,
+                        mask: CollisionLayers::NONE,
+                    }),
+                })
+            },
+            Tile::Coin => {
+                Some(TileInfo {
+                    tile_type: tile,
+                    position: position(1.0),
+                    image: SpriteVariant::SpriteSheet(SpriteType::Coin),
+                    hitbox: Some(Hitbox {
+                        relative_position: Vec3::default(),
+                        size: Vec2::new(Tile::SIZE / 2.0, Tile::SIZE / 2.0),
+                        layers: CollisionLayers::COIN,
+                        mask: CollisionLayers::NONE,
                     }),
                 })
             },
@@ -150,138 +233,151 @@ impl Map {
                     tile_type: tile,
                     position: position(1.0),
                     image: SpriteVariant::SpriteSheet(name),
-                    hitbox: None,
+                    hitbox: Some(Hitbox { // the range the player can talk to the npc from
+                        relative_position: Vec3::default(),
+                        size: Vec2::new(3.0 * Tile::SIZE, Tile::SIZE),
+                        layers: CollisionLayers::NPC,
+                        mask: CollisionLayers::NONE,
+                    }),
                 })
             },
         }
     }
-    pub fn tile_info_iter(&self) -> impl Iterator<Item = Option<TileInfo>> + '_ {
-        self.iter().map(|(i, j)| self.get_tile_info(i, j))
+    pub fn tile_info_iter(&self) -> impl Iterator<Item = ((usize, usize), Option<TileInfo>)> + '_ {
+        self.iter().map(|(i, j)| ((i, j), self.get_tile_info(i, j)))
     }
     pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
-        (0..Map::WIDTH).flat_map(|i| (0..Map::HEIGHT).map(move |j| (i, j)))
+        let height = self.height;
+        (0..self.width).flat_map(move |i| (0..height).map(move |j| (i, j)))
+    }
+}
+
+// the whitespace separated tokens of a line from character `from` on, with the column each one starts at
+fn tokens(line: &str, from: usize) -> Vec<(usize, &str)> {
+    let mut tokens = Vec::new();
+    let mut token = None; // column and byte offset of the token being read
+    for (column, (offset, c)) in line.char_indices().enumerate().skip(from) {
+        match (c.is_whitespace(), token) {
+            (false, None) => token = Some((column, offset)),
+            (true, Some((start_column, start))) => {
+                tokens.push((start_column, &line[start..offset]));
+                token = None;
+            },
+            _ => {},
+        }
+    }
+    if let Some((column, start)) = token {
+        tokens.push((column, &line[start..]));
     }
+    tokens
 }
 
-impl std::ops::Deref for Map {
-    type Target = [Line; 100];
-    fn deref(&self) -> &Self::Target {
-        &self.values
+// exactly `count` values, or the column and text of the first token that is wrong or extra, or of the end of the line
+// when some are missing
+fn parse_values<T: FromStr>(tokens: &[(usize, &str)], count: usize, end: usize) -> Result<Vec<T>, (usize, String)> {
+    let mut values = Vec::new();
+    for (index, &(column, token)) in tokens.iter().enumerate() {
+        let value = token.parse().ok()
+            .filter(|_| index < count)
+            .ok_or_else(|| (column, token.to_string()))?;
+        values.push(value);
     }
+    if values.len() < count {
+        return Err((end, String::new()));
+    }
+    Ok(values)
 }
 
-impl std::ops::Index<usize> for Map {
-    type Output = Line;
-    fn index(&self, i: usize) -> &Self::Output {
-        &self.values[i]
+impl std::ops::Index<(usize, usize)> for Map {
+    type Output = Tile;
+    fn index(&self, (i, j): (usize, usize)) -> &Self::Output {
+        &self.values[i * self.height + j]
     }
 }
 
-pub fn read_map() -> Map {
-    //TODO: read map from a file
-    LEVEL_0
+#[derive(Debug, PartialEq)]
+pub enum MapErrorKind {
+    Empty,
+    UnknownTile(char),
+    WrongWidth { width: usize, expected: usize },
+    UnknownMetadata(String),
+    InvalidMetadata(String),
 }
 
-const LEVEL_0: Map = Map { values: [
-    //turn your head to the right to read this
-    [Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Player, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Blue, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Npc(SpriteType::Pink), Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Npc(SpriteType::Yellow), Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Npc(SpriteType::Jeremy), Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Npc(SpriteType::Block), Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty],
-    [Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-    [Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Ground, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty, Tile::Empty],
-]};
\ No newline at end of file
+#[derive(Debug)]
+pub struct MapError {
+    pub path: PathBuf,
+    pub row: usize,
+    pub column: usize,
+    pub kind: MapErrorKind,
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}:{}: ", self.path.display(), self.row, self.column)?;
+        match &self.kind {
+            MapErrorKind::Empty => write!(f, "level has no tiles"),
+            MapErrorKind::UnknownTile(c) => write!(f, "unknown tile '{}'", c),
+            MapErrorKind::WrongWidth { width, expected } => write!(f, "row is {} tiles wide, expected {}", width, expected),
+            MapErrorKind::UnknownMetadata(key) => write!(f, "unknown metadata key '{}'", key),
+            MapErrorKind::InvalidMetadata(value) => write!(f, "invalid metadata value '{}'", value),
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<Map, MapError> {
+        Map::parse(Path::new("test.level"), text)
+    }
+
+    fn error(text: &str) -> (usize, usize, MapErrorKind) {
+        let error = parse(text).err().unwrap();
+        (error.row, error.column, error.kind)
+    }
+
+    #[test]
+    fn parses_metadata_and_tiles() {
+        let map = parse("origin: -1 2.5\nwall_jump: true\n@.c\n###").unwrap();
+        assert_eq!((map.width, map.height), (3, 2));
+        assert_eq!(map.origin, Vec2::new(-1.0, 2.5));
+        assert!(map.wall_jump());
+        assert!(map[(0, 1)] == Tile::Player(0));
+        assert!(map[(2, 1)] == Tile::Coin);
+        assert!(map[(1, 0)] == Tile::Ground);
+    }
+
+    #[test]
+    fn unknown_tile() {
+        assert_eq!(error("...\n.x.\n###"), (2, 2, MapErrorKind::UnknownTile('x')));
+    }
+
+    #[test]
+    fn wrong_width() {
+        assert_eq!(error("...\n..\n###"), (2, 3, MapErrorKind::WrongWidth { width: 2, expected: 3 }));
+        assert_eq!(error("...\n....\n###"), (2, 4, MapErrorKind::WrongWidth { width: 4, expected: 3 }));
+    }
+
+    #[test]
+    fn bad_metadata_points_at_the_token() {
+        assert_eq!(error("origin: 3 x 4\n#"), (1, 11, MapErrorKind::InvalidMetadata("x".to_string())));
+        assert_eq!(error("origin: 1 2 3\n#"), (1, 13, MapErrorKind::InvalidMetadata("3".to_string())));
+        assert_eq!(error("origin: 1\n#"), (1, 10, MapErrorKind::InvalidMetadata(String::new())));
+        assert_eq!(error("wall_jump: maybe\n#"), (1, 12, MapErrorKind::InvalidMetadata("maybe".to_string())));
+    }
+
+    #[test]
+    fn unknown_metadata() {
+        assert_eq!(error("origin: 0 0\ngravity: 3\n#"), (2, 1, MapErrorKind::UnknownMetadata("gravity".to_string())));
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(error(""), (1, 1, MapErrorKind::Empty));
+        assert_eq!(error("wall_jump: false"), (2, 1, MapErrorKind::Empty));
+    }
+}
@@ -1,18 +1,59 @@
+use std::collections::HashMap;
+
 use bevy::prelude::*;
+use bevy::transform::TransformSystem;
 
-use crate::camera::MainCamera;
-use crate::controls::Controls;
+use crate::animation::{animate, Animator};
+use crate::camera::{reset_camera, MainCamera};
+use crate::controls::{Action, ActionState, ActionSystem, Controls};
+use crate::hot_reload::update_resource;
+use crate::save::SaveData;
 use crate::state::AppState;
 use crate::sprite::*;
 
+mod autotile;
+
+mod coin;
+pub use coin::Score;
+use coin::*;
+
 mod direction;
 
+mod enemy;
+use enemy::*;
+
+mod grid;
+pub use grid::CollisionGrid;
+use grid::*;
+
+mod health;
+use health::Health;
+
 mod hitbox;
+pub use hitbox::Hitbox;
 use hitbox::*;
 
+mod hud;
+use hud::*;
+
+mod level;
+pub use level::{LevelEntity, LevelHandles, LevelTime};
+use level::{LevelLoader, reset_level_time, tick_level_time};
+
 mod map;
+pub use map::{Map, Tile};
 use map::*;
 
+mod npc;
+use npc::*;
+
+mod physics;
+use physics::*;
+
+mod physics_config;
+pub use physics_config::PhysicsConfigHandle;
+use physics_config::*;
+
 mod player;
 use player::*;
 
@@ -21,26 +62,94 @@ use velocity::*;
 
 pub struct Game;
 
+// the level ended, ordered by which one wins when several happen in the same frame
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum LevelEnd {
+    GameOver,
+    Complete,
+}
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LevelEndSystem;
+
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+enum GameSystem {
+    BeginStep,
+    Input,
+    Movement,
+    Collision,
+    CollisionResponse,
+    EndStep,
+    Interpolation,
+}
+
 impl Plugin for Game {
     fn build(&self, app: &mut App) {
         app
+            .add_stage_after(CoreStage::PreUpdate, PhysicsStage, SystemStage::parallel())
+            .init_resource::<PhysicsTime>()
+            .add_asset::<PhysicsConfig>()
+            .init_asset_loader::<PhysicsConfigLoader>()
+            .init_resource::<PhysicsConfig>()
+            .init_resource::<PhysicsConfigHandle>()
+            .add_system(update_resource::<PhysicsConfig>)
+            .add_asset::<Map>()
+            .init_asset_loader::<LevelLoader>()
+            .init_resource::<LevelHandles>()
+            .init_resource::<LevelTime>()
+            .init_resource::<Score>()
+            .add_event::<CollisionEvent>()
+            .add_event::<LevelEnd>()
+            .init_resource::<CollisionGrid>()
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(reset_camera))
             .add_system_set(SystemSet::on_enter(AppState::Game).with_system(spawn_background))
             .add_system_set(SystemSet::on_enter(AppState::Game).with_system(load_level))
-            .add_system_set(SystemSet::on_update(AppState::Game).with_system(animation))
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(spawn_hud))
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(reset_level_time))
+            .add_system_set(SystemSet::on_enter(AppState::Game).with_system(reset_score))
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(animate))
             .add_system_set(SystemSet::on_update(AppState::Game).with_system(update_direction))
             .add_system_set(SystemSet::on_update(AppState::Game).with_system(player_spritesheet))
-            .add_system_set(SystemSet::on_update(AppState::Game).with_system(input))
-            .add_system_set(SystemSet::on_update(AppState::Game).with_system(player_ground_collision))
-            .add_system_set(SystemSet::on_update(AppState::Game).with_system(player_enemy_collision))
-            .add_system_set(SystemSet::on_update(AppState::Game).with_system(movement))
-            .add_system_set(SystemSet::on_update(AppState::Game).with_system(camera_movement))
-            .add_system_set(SystemSet::on_update(AppState::Game).with_system(out_of_bounds));
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(player_goal_collision.before(LevelEndSystem)))
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(interact))
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(invulnerability))
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(update_hud))
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(update_score_text))
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(out_of_bounds.before(LevelEndSystem)))
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(end_level.label(LevelEndSystem)))
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(tick_level_time))
+            .add_system_set_to_stage(CoreStage::PreUpdate, SystemSet::new().with_run_criteria(in_game).with_system(read_input.after(ActionSystem)))
+            .add_system_set_to_stage(CoreStage::PreUpdate, SystemSet::new().with_run_criteria(in_game).with_system(restore_physics_position))
+            .add_system_set_to_stage(CoreStage::PreUpdate, SystemSet::new().with_run_criteria(in_game).with_system(accumulate_physics_time))
+            .add_system_set_to_stage(PhysicsStage, physics_systems())
+            .add_system_set_to_stage(CoreStage::PostUpdate, SystemSet::new().with_run_criteria(in_game)
+                .with_system(interpolate.label(GameSystem::Interpolation).before(TransformSystem::TransformPropagate))
+                .with_system(camera_movement.after(GameSystem::Interpolation).before(TransformSystem::TransformPropagate)))
+            .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(despawn_level))
+            .add_system_set(SystemSet::on_exit(AppState::LevelComplete).with_system(despawn_level));
     }
 }
 
+// everything that runs once per fixed physics step
+fn physics_systems() -> SystemSet {
+    SystemSet::new().with_run_criteria(physics_step)
+        .with_system(begin_step.label(GameSystem::BeginStep))
+        .with_system(apply_input.label(GameSystem::Input).after(GameSystem::BeginStep))
+        .with_system(patrol.label(GameSystem::Input).after(GameSystem::BeginStep))
+        .with_system(update_collision_grid.before(GameSystem::Movement))
+        .with_system(movement.label(GameSystem::Movement).after(GameSystem::Input))
+        .with_system(detect_collisions.label(GameSystem::Collision).after(GameSystem::Movement))
+        .with_system(ground_collision.label(GameSystem::CollisionResponse).after(GameSystem::Collision))
+        .with_system(player_landing.after(GameSystem::CollisionResponse).before(GameSystem::EndStep))
+        .with_system(patrol_turn.after(GameSystem::CollisionResponse).before(GameSystem::EndStep))
+        .with_system(player_enemy_collision.after(GameSystem::CollisionResponse).before(GameSystem::EndStep))
+        .with_system(player_coin_collision.after(GameSystem::CollisionResponse).before(GameSystem::EndStep))
+        .with_system(end_step.label(GameSystem::EndStep).after(GameSystem::CollisionResponse))
+}
+
 fn spawn_background(
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
+    sprite_handles: Res<SpriteHandles>,
 ) {
     let tile_size = 24.0;
     let layer = 0.0;
@@ -49,13 +158,16 @@ fn spawn_background(
             //TODO: use one big image looping or just moving with the camera instead of creating a trillion entities
             let cloud_height = 3;
             let image = if j < cloud_height { SpriteTypeStates::Full } else if j == cloud_height { SpriteTypeStates::Half } else { SpriteTypeStates::Empty };
-            let image = SPRITES[&SpriteType::BlueBG][&image];
+            let (texture_atlas, sprite, pivot) = sprite_handles.frame(SpriteType::BlueBG, image);
             commands
-                .spawn_bundle(SpriteBundle {
-                    texture: asset_server.get_handle(image),
+                .spawn_bundle(SpriteSheetBundle {
+                    texture_atlas,
+                    sprite,
                     transform: Transform::from_translation(Vec3::new(i as f32*tile_size, j as f32*tile_size, layer)),
                     ..Default::default()
-                });
+                })
+                .insert(pivot)
+                .insert(LevelEntity);
         }
     }
 }
@@ -63,56 +175,75 @@ fn spawn_background(
 fn load_level(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    sprites: Res<SpriteManifest>,
     sprite_handles: Res<SpriteHandles>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-    mut textures: ResMut<Assets<Image>>,
+    level_handles: Res<LevelHandles>,
+    maps: Res<Assets<Map>>,
+    save: Res<SaveData>,
 ) {
-    let mut spawn = |name| {
-        spawn(
-            name,
-            &sprite_handles,
-            &mut texture_atlases,
-            &mut textures,
-        )
-    };
-    for tile_info in read_map().tile_info_iter() {
+    let map = maps.get(level_handles.current()).unwrap();
+    for (position, tile_info) in map.tile_info_iter() {
         if let Some(tile_info) = tile_info {
+            // coins only count once, even across replays of the level
+            if tile_info.tile_type == Tile::Coin && save.is_collected(level_handles.current, position) {
+                continue;
+            }
             let mut entity = commands.spawn();
+            entity.insert(LevelEntity);
             match tile_info.image {
-                SpriteVariant::Sprite(path) => entity.insert_bundle(SpriteBundle {
-                        texture: asset_server.get_handle(path),
+                SpriteVariant::Sprite(key, state) => {
+                    let (texture_atlas, sprite, pivot) = sprite_handles.frame(key, state);
+                    entity.insert_bundle(SpriteSheetBundle {
+                        texture_atlas,
+                        sprite,
                         transform: Transform::from_translation(tile_info.position),
                         ..Default::default()
-                    }),
-                SpriteVariant::SpriteSheet(key) => entity.insert_bundle(SpriteSheetBundle {
-                        texture_atlas: spawn(key.to_string()),
+                    })
+                    .insert(pivot)
+                },
+                SpriteVariant::SpriteSheet(key) => {
+                    let (texture_atlas, sprite, animator, pivot) = sprite_handles.sheet(key);
+                    entity.insert_bundle(SpriteSheetBundle {
+                        texture_atlas,
+                        sprite,
                         transform: Transform::from_translation(tile_info.position),
                         ..Default::default()
                     })
-                    .insert(SpriteTimer::from_seconds(0.2)),
+                    .insert(animator)
+                    .insert(pivot)
+                },
             };
             if let Some(hitbox) = tile_info.hitbox {
                 match tile_info.tile_type {
                     Tile::Empty => panic!("Not possible to have a hitbox on an empty tile"),
-                    Tile::Ground => { entity.insert(GroundHitbox(hitbox)); },
-                    Tile::Player => {
+                    Tile::Ground => { entity.insert(hitbox); },
+                    Tile::Player(player) => {
                         entity.insert_bundle(PlayerBundle {
-                            ground_hitbox: PlayerGroundHitbox(hitbox.clone()),
-                            enemy_hitbox: PlayerEnemyHitbox(hitbox),
+                            player_character: PlayerCharacter::new(Character::for_player(player), map.wall_jump()),
+                            controls: Controls { player },
+                            hitbox,
+                            interpolation: Interpolation::new(tile_info.position),
                             ..Default::default()
                         });
                     },
                     Tile::Blue => {
-                        entity.insert(EnemyHitbox(hitbox));
+                        entity.insert_bundle(EnemyBundle {
+                            hitbox,
+                            direction: direction::Direction::Left,
+                            interpolation: Interpolation::new(tile_info.position),
+                            ..Default::default()
+                        });
                         entity.with_children(|parent| {
                             parent.spawn_bundle(SpriteBundle {
-                                texture: asset_server.get_handle("torch-light-effect.png"),
+                                texture: asset_server.get_handle(sprites.path(SpriteType::TorchLight, SpriteTypeStates::Idle)),
                                 ..Default::default()
                             });
                         });
                     },
-                    Tile::Npc(_) => {
-                        todo!()
+                    Tile::Goal => { entity.insert(hitbox); },
+                    Tile::Coin => { entity.insert(hitbox).insert(Coin { position }); },
+                    Tile::Npc(name) => {
+                        entity.insert(hitbox).insert(Npc::new(name));
                     },
                 }
             }
@@ -120,20 +251,6 @@ fn load_level(
     }
 }
 
-fn animation(
-    time: Res<Time>,
-    texture_atlases: Res<Assets<TextureAtlas>>,
-    mut query: Query<(&mut SpriteTimer, &mut TextureAtlasSprite, &Handle<TextureAtlas>)>,
-) {
-    for (mut sprite_timer, mut sprite, texture_atlas_handle) in query.iter_mut() {
-        sprite_timer.timer.tick(time.delta());
-        if sprite_timer.timer.finished() {
-            let texture_atlas = texture_atlases.get(texture_atlas_handle).unwrap();
-            sprite.index = (sprite.index + 1) % texture_atlas.textures.len();
-        }
-    }
-}
-
 fn update_direction(mut query: Query<(&mut TextureAtlasSprite, &direction::Direction)>) {
     for (mut sprite, direction) in query.iter_mut() {
         sprite.flip_x = *direction == direction::Direction::Right;
@@ -142,140 +259,553 @@ fn update_direction(mut query: Query<(&mut TextureAtlasSprite, &direction::Direc
 
 fn player_spritesheet(
     sprite_handles: Res<SpriteHandles>,
-    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
-    mut textures: ResMut<Assets<Image>>,
-    mut query: Query<(&mut PlayerCharacter, &mut TextureAtlasSprite, &mut Handle<TextureAtlas>)>,
+    mut query: Query<(&mut PlayerCharacter, &mut TextureAtlasSprite, &mut Handle<TextureAtlas>, &mut Animator, &mut Pivot)>,
 ) {
-    for (mut player, mut sprite, mut texture_atlas_handle) in query.iter_mut() {
+    for (mut player, mut sprite, mut texture_atlas_handle, mut animator, mut pivot) in query.iter_mut() {
         if let Some(sheet) = player.update_spritesheet() {
-            *texture_atlas_handle = spawn(sheet.to_string(), &sprite_handles, &mut texture_atlases, &mut textures);
-            *sprite = TextureAtlasSprite::default();
+            let (texture_atlas, new_sprite, new_animator, new_pivot) = sprite_handles.sheet(sheet);
+            *texture_atlas_handle = texture_atlas;
+            *sprite = new_sprite;
+            *animator = new_animator;
+            *pivot = new_pivot;
+        }
+    }
+}
+
+fn read_input(
+    actions: Res<ActionState>,
+    mut query: Query<(&Controls, &mut PlayerInput)>,
+) {
+    for (&controls, mut player_input) in query.iter_mut() {
+        player_input.direction = direction::Direction::from_input(actions.pressed(controls, Action::Left), actions.pressed(controls, Action::Right));
+        if actions.just_pressed(controls, Action::Jump) {
+            player_input.jump_pressed = true;
+        }
+        if actions.just_released(controls, Action::Jump) {
+            player_input.jump_released = true;
         }
     }
 }
 
-fn input(
-    input: Res<Input<KeyCode>>,
-    mut query: Query<(&mut PlayerCharacter, &Controls, &mut Velocity, &mut direction::Direction)>,
+fn apply_input(
+    config: Res<PhysicsConfig>,
+    mut query: Query<(&mut PlayerCharacter, &mut PlayerInput, &mut Velocity, &mut direction::Direction)>,
 ) {
-    for (mut player, controls, mut velocity, mut direction) in query.iter_mut() {
-        let new_direction = direction::Direction::from_input(input.pressed(controls.left), input.pressed(controls.right));
-        velocity.update(new_direction);
-        if let Some(new_direction) = new_direction {
+    for (mut player, mut player_input, mut velocity, mut direction) in query.iter_mut() {
+        player.tick(PHYSICS_STEP);
+        velocity.update(&config, player_input.direction, PHYSICS_STEP);
+        if let Some(new_direction) = player_input.direction {
             *direction = new_direction;
         }
         player.update_walk_state(velocity.0.x);
 
-        if input.just_pressed(controls.jump) {
-            if let Ok(_) = player.try_jump() {
-                velocity.0.y = 500.0;
-            }
+        if std::mem::take(&mut player_input.jump_pressed) {
+            player.press_jump(&config);
+        }
+        match player.try_jump(&config) {
+            Ok(Jump::Normal) => velocity.0.y = config.jump_speed,
+            Ok(Jump::Wall(away)) => {
+                velocity.0.x = config.wall_jump_speed.x * f32::from(away);
+                velocity.0.y = config.wall_jump_speed.y;
+                *direction = away;
+            },
+            Err(_) => {},
+        }
+        if player.is_wall_sliding() {
+            velocity.0.y = velocity.0.y.max(-config.wall_slide_speed);
+        }
+        if std::mem::take(&mut player_input.jump_released) && player.is_jumping() && velocity.0.y > 0.0 {
+            velocity.0.y *= config.jump_cut;
         }
     }
 }
 
 fn movement(
-    time: Res<Time>,
-    mut query: Query<(&mut Velocity, &mut Transform)>,
+    config: Res<PhysicsConfig>,
+    grid: Res<CollisionGrid>,
+    solid_query: Query<(&Hitbox, &Transform), Without<Velocity>>,
+    mut query: Query<(&mut Velocity, &mut Transform, Option<&Hitbox>, Option<&mut GroundContacts>)>,
 ) {
-    for (mut velocity, mut transform) in query.iter_mut() {
-        velocity.apply_gravity(time.delta_seconds());
-        transform.translation += velocity.0 * time.delta_seconds();
+    for (mut velocity, mut transform, hitbox, contacts) in query.iter_mut() {
+        velocity.apply_gravity(&config, PHYSICS_STEP);
+        let displacement = velocity.0.truncate() * PHYSICS_STEP;
+        let (hitbox, mut contacts) = match (hitbox, contacts) {
+            (Some(hitbox), Some(contacts)) => (hitbox, contacts),
+            _ => {
+                transform.translation += displacement.extend(0.0);
+                continue;
+            },
+        };
+        let (min, max) = hitbox.bounds(&transform.translation);
+        let solids: Vec<(Vec2, Vec2)> = grid.query(min.min(min + displacement), max.max(max + displacement))
+            .into_iter()
+            .filter_map(|entity| solid_query.get(entity).ok())
+            .filter(|(solid_hitbox, _)| solid_hitbox.layers.intersects(CollisionLayers::GROUND))
+            .map(|(solid_hitbox, solid_transform)| solid_hitbox.bounds(&solid_transform.translation))
+            .collect();
+        let sweep = sweep(min, max, displacement, &solids);
+        transform.translation += sweep.displacement.extend(0.0);
+        *contacts = sweep.contacts;
+        if contacts.bottom && velocity.0.y < 0.0 {
+            velocity.0.y = 0.0;
+        }
+        if contacts.top {
+            velocity.stop_top();
+        }
+        if contacts.left {
+            velocity.stop_left();
+        }
+        if contacts.right {
+            velocity.stop_right();
+        }
     }
 }
 
+// follows the players horizontally, zooming out when they are too far apart to fit in the dead zone
 fn camera_movement(
     windows: Res<Windows>,
     player_query: Query<(&PlayerCharacter, &Transform)>,
-    mut camera_query: Query<(&MainCamera, &mut Transform), Without<PlayerCharacter>>,
+    mut camera_query: Query<(&MainCamera, &mut Transform, &mut OrthographicProjection), Without<PlayerCharacter>>,
 ) {
-    let window = windows.get_primary().unwrap();
-    let horizontal_limit = window.width() * 0.3;
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let dead_zone = 0.3;
 
-    let (_, player_position) = player_query.single();
-    let player_position = player_position.translation.x;
+    let (left_player, right_player) = player_query.iter()
+        .map(|(_, transform)| transform.translation.x)
+        .fold((f32::MAX, f32::MIN), |(left, right), x| (left.min(x), right.max(x)));
+    if left_player > right_player {
+        return;
+    }
 
-    let (_, mut camera_position) = camera_query.single_mut();
+    let (_, mut camera_position, mut projection) = camera_query.single_mut();
+    projection.scale = ((right_player - left_player) / (2.0 * dead_zone * window.width())).max(1.0);
+    let horizontal_limit = dead_zone * window.width() * projection.scale;
 
     let left_limit = camera_position.translation.x - horizontal_limit;
     let right_limit = camera_position.translation.x + horizontal_limit;
 
-    if player_position < left_limit {
-        camera_position.translation.x = player_position + horizontal_limit;
-    } else if player_position > right_limit {
-        camera_position.translation.x = player_position - horizontal_limit;
+    if left_player < left_limit {
+        camera_position.translation.x = left_player + horizontal_limit;
+    } else if right_player > right_limit {
+        camera_position.translation.x = right_player - horizontal_limit;
     }
 }
 
-fn player_ground_collision(
-    ground_query: Query<(&GroundHitbox, &Transform), Without<PlayerGroundHitbox>>,
-    mut player_query: Query<(&mut PlayerCharacter, &PlayerGroundHitbox, &mut Transform, &mut Velocity), Without<GroundHitbox>>,
+// pushes hitboxes out of any ground they still overlap after movement, e.g. after spawning inside a wall
+fn ground_collision(
+    mut events: EventReader<CollisionEvent>,
+    ground_query: Query<(&Hitbox, &Transform), Without<Velocity>>,
+    mut query: Query<(&Hitbox, &mut Transform, &mut Velocity, &mut GroundContacts)>,
 ) {
-    for (mut player, player_hitbox, mut player_transform, mut player_velocity) in player_query.iter_mut() {
-        for (ground_hitbox, ground_transform) in ground_query.iter() {
-            if let Some(collision) = player_hitbox.0.collide(&player_transform.translation, &ground_hitbox.0, &ground_transform.translation) {
+    let mut collisions: HashMap<Entity, Vec<&CollisionEvent>> = HashMap::new();
+    for event in events.iter().filter(|event| event.other_layers.intersects(CollisionLayers::GROUND)) {
+        collisions.entry(event.entity).or_default().push(event);
+    }
+    for (entity, events) in collisions {
+        let (hitbox, mut transform, mut velocity, mut contacts) = match query.get_mut(entity) {
+            Ok(components) => components,
+            Err(_) => continue,
+        };
+        // resolve vertical collisions first, so that walking over the seam between two tiles doesn't count as hitting a wall
+        let (vertical, horizontal): (Vec<_>, Vec<_>) = events.into_iter()
+            .partition(|event| matches!(event.collision_type, CollisionType::Bottom | CollisionType::Top));
+        let max_overlap = |collision_type| vertical.iter()
+            .filter(|event| event.collision_type == collision_type)
+            .map(|event| event.overlap)
+            .fold(0.0, f32::max);
+        let bottom_overlap = max_overlap(CollisionType::Bottom);
+        if bottom_overlap > 0.0 {
+            transform.translation.y += bottom_overlap;
+            if velocity.0.y < 0.0 {
+                velocity.0.y = 0.0;
+                contacts.bottom = true;
+            }
+        }
+        let top_overlap = max_overlap(CollisionType::Top);
+        if top_overlap > 0.0 {
+            transform.translation.y -= top_overlap;
+            velocity.stop_top();
+            contacts.top = true;
+        }
+        for event in horizontal {
+            let (ground_hitbox, ground_transform) = match ground_query.get(event.other) {
+                Ok(components) => components,
+                Err(_) => continue,
+            };
+            if let Some(collision) = hitbox.collide(&transform.translation, ground_hitbox, &ground_transform.translation) {
                 match collision.collision_type {
-                    CollisionType::Bottom => {
-                        player_transform.translation.y += collision.overlap;
-                        if player_velocity.0.y < 0.0 {
-                            player_velocity.0.y = 0.0;
-                            player.hit_ground();
-                        }
-                    },
-                    CollisionType::Top => {
-                        player_transform.translation.y -= collision.overlap;
-                        player_velocity.stop_top();
-                    },
                     CollisionType::Left => {
-                        player_transform.translation.x += collision.overlap;
-                        player_velocity.stop_left();
+                        transform.translation.x += collision.overlap;
+                        velocity.stop_left();
+                        contacts.left = true;
                     },
                     CollisionType::Right => {
-                        player_transform.translation.x -= collision.overlap;
-                        player_velocity.stop_right();
+                        transform.translation.x -= collision.overlap;
+                        velocity.stop_right();
+                        contacts.right = true;
                     },
+                    _ => {},
                 };
             }
         }
     }
 }
 
+fn player_landing(
+    config: Res<PhysicsConfig>,
+    mut query: Query<(&mut PlayerCharacter, &PlayerInput, &Velocity, &GroundContacts)>,
+) {
+    for (mut player, player_input, velocity, contacts) in query.iter_mut() {
+        player.update_contacts(contacts, player_input.direction, velocity.0.y <= 0.0, &config);
+    }
+}
+
+fn patrol(mut query: Query<(&Patrol, &mut Velocity, &direction::Direction)>) {
+    for (patrol, mut velocity, direction) in query.iter_mut() {
+        velocity.0.x = patrol.speed * f32::from(*direction);
+    }
+}
+
+fn patrol_turn(
+    grid: Res<CollisionGrid>,
+    ground_query: Query<(&Hitbox, &Transform), Without<Patrol>>,
+    mut enemy_query: Query<(&Patrol, &Hitbox, &Transform, &GroundContacts, &mut direction::Direction)>,
+) {
+    for (patrol, hitbox, transform, contacts, mut direction) in enemy_query.iter_mut() {
+        if patrol.turn_at_wall && contacts.left {
+            *direction = direction::Direction::Right;
+        } else if patrol.turn_at_wall && contacts.right {
+            *direction = direction::Direction::Left;
+        } else if patrol.turn_at_ledge && contacts.bottom {
+            let (min, max) = hitbox.bounds(&transform.translation);
+            let front = if *direction == direction::Direction::Right { max.x + 1.0 } else { min.x - 1.0 };
+            let ahead = Vec2::new(front, min.y - 1.0);
+            let ground_ahead = grid.query(ahead, ahead).into_iter()
+                .filter_map(|entity| ground_query.get(entity).ok())
+                .any(|(ground_hitbox, ground_transform)| {
+                    ground_hitbox.layers.intersects(CollisionLayers::GROUND) && ground_hitbox.contains(&ground_transform.translation, ahead)
+                });
+            if !ground_ahead {
+                *direction = direction.opposite();
+            }
+        }
+    }
+}
+
 fn player_enemy_collision(
-    mut state: ResMut<State<AppState>>,
     mut commands: Commands,
-    enemy_query: Query<(Entity, &EnemyHitbox, &Transform), Without<PlayerGroundHitbox>>,
-    mut player_query: Query<(&PlayerCharacter, &PlayerGroundHitbox, &Transform, &mut Velocity), Without<GroundHitbox>>,
+    mut events: EventReader<CollisionEvent>,
+    mut level_end: EventWriter<LevelEnd>,
+    mut player_query: Query<(&mut Velocity, &mut Health), With<PlayerCharacter>>,
 ) {
-    for (_, player_hitbox, player_transform, mut player_velocity) in player_query.iter_mut() {
-        for (enemy_id, enemy_hitbox, enemy_transform) in enemy_query.iter() {
-            if let Some(collision) = player_hitbox.0.collide(&player_transform.translation, &enemy_hitbox.0, &enemy_transform.translation) {
-                match collision.collision_type {
-                    CollisionType::Bottom => {
-                        //TODO: change player and enemy states so that some animation plays or there is a chance to jump again or something
-                        commands.entity(enemy_id).despawn_recursive();
-                        player_velocity.0.y *= -1.0;
-                    },
-                    _ => { state.set(AppState::GameOver).unwrap(); },
+    let mut died = false;
+    for event in events.iter().filter(|event| event.other_layers.intersects(CollisionLayers::ENEMY)) {
+        let (mut player_velocity, mut health) = match player_query.get_mut(event.entity) {
+            Ok(components) if !components.1.is_dead() => components,
+            _ => continue,
+        };
+        match event.collision_type {
+            CollisionType::Bottom => {
+                //TODO: change player and enemy states so that some animation plays or there is a chance to jump again or something
+                commands.entity(event.other).despawn_recursive();
+                player_velocity.0.y *= -1.0;
+            },
+            collision_type => {
+                if health.damage(1).is_err() {
+                    continue;
+                }
+                if health.is_dead() {
+                    commands.entity(event.entity).despawn_recursive();
+                    died = true;
+                    continue;
+                }
+                let knockback = 200.0;
+                player_velocity.0 = match collision_type {
+                    CollisionType::Left => Vec3::new(knockback, knockback, 0.0),
+                    CollisionType::Right => Vec3::new(-knockback, knockback, 0.0),
+                    _ => Vec3::new(player_velocity.0.x, -knockback, 0.0),
                 };
-            }
-        }
+            },
+        };
+    }
+    // in co-op the others play on, players who died are only despawned once the step is over
+    if died && player_query.iter().all(|(_, health)| health.is_dead()) {
+        level_end.send(LevelEnd::GameOver);
+    }
+}
+
+fn invulnerability(
+    time: Res<Time>,
+    mut query: Query<(&mut Health, &mut Visibility)>,
+) {
+    for (mut health, mut visibility) in query.iter_mut() {
+        health.tick(time.delta());
+        visibility.is_visible = !health.is_blinking();
+    }
+}
+
+fn player_goal_collision(
+    mut events: EventReader<CollisionEvent>,
+    mut level_end: EventWriter<LevelEnd>,
+) {
+    if events.iter().any(|event| event.other_layers.intersects(CollisionLayers::GOAL)) {
+        level_end.send(LevelEnd::Complete);
+    }
+}
+
+fn despawn_level(
+    mut commands: Commands,
+    query: Query<Entity, With<LevelEntity>>,
+) {
+    for id in query.iter() {
+        commands.entity(id).despawn_recursive();
     }
 }
 
 fn out_of_bounds(
-    mut state: ResMut<State<AppState>>,
+    mut commands: Commands,
+    mut level_end: EventWriter<LevelEnd>,
     windows: Res<Windows>,
-    player_query: Query<(&PlayerCharacter, &Transform)>,
-    camera_query: Query<(&MainCamera, &Transform), Without<PlayerCharacter>>,
+    player_query: Query<(Entity, &Transform), With<PlayerCharacter>>,
+    camera_query: Query<(&MainCamera, &Transform, &OrthographicProjection), Without<PlayerCharacter>>,
 ) {
-    let (_, camera_position) = camera_query.single();
+    let (_, camera_position, projection) = camera_query.single();
 
-    let window = windows.get_primary().unwrap();
-    let screen_bottom = camera_position.translation.y - window.height() / 2.0;
+    let window = match windows.get_primary() {
+        Some(window) => window,
+        None => return,
+    };
+    let screen_bottom = camera_position.translation.y - window.height() * projection.scale / 2.0;
 
-    for (_, transform) in player_query.iter() {
+    let mut fell = false;
+    let mut remaining = 0;
+    for (entity, transform) in player_query.iter() {
         if transform.translation.y < screen_bottom {
-            state.set(AppState::GameOver).unwrap();
+            commands.entity(entity).despawn_recursive();
+            fell = true;
+        } else {
+            remaining += 1;
         }
     }
-}
\ No newline at end of file
+    if fell && remaining == 0 {
+        level_end.send(LevelEnd::GameOver);
+    }
+}
+
+// the only system leaving the game for another screen, so a single transition gets queued per frame
+fn end_level(
+    mut state: ResMut<State<AppState>>,
+    mut events: EventReader<LevelEnd>,
+) {
+    let next_state = match events.iter().max() {
+        Some(LevelEnd::GameOver) => AppState::GameOver,
+        Some(LevelEnd::Complete) => AppState::LevelComplete,
+        None => return,
+    };
+    state.set(next_state).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use bevy::asset::AssetPlugin;
+    use bevy::input::InputPlugin;
+
+    use crate::animation::{Animation, AnimationPlugin};
+    use crate::controls::{Bindings, ControlsPlugin};
+    use crate::menu::MenuPlugin;
+    use crate::sprite::SpritePlugin;
+    use crate::state::GameOver;
+
+    use super::*;
+
+    // stands in for Time, whose delta can't be set from outside bevy
+    struct FrameTime(f32);
+
+    #[derive(Default)]
+    struct Steps(usize);
+
+    fn advance_frame(frame_time: Res<FrameTime>, mut physics_time: ResMut<PhysicsTime>) {
+        physics_time.advance(frame_time.0);
+    }
+
+    // runs right for a second, jumping after half a second and letting go of jump 0.2 seconds later
+    fn scripted_input(mut steps: ResMut<Steps>, mut query: Query<&mut PlayerInput>) {
+        let step = steps.0;
+        steps.0 += 1;
+        for mut input in query.iter_mut() {
+            input.direction = if step < 60 { Some(direction::Direction::Right) } else { None };
+            input.jump_pressed |= step == 30;
+            input.jump_released |= step == 42;
+        }
+    }
+
+    // a player standing on a strip of ground, run by the real physics systems
+    fn physics_app(frame_time: f32) -> App {
+        let mut app = App::new();
+        app
+            .insert_resource(State::new(AppState::Game))
+            .insert_resource(FrameTime(frame_time))
+            .init_resource::<PhysicsConfig>()
+            .init_resource::<PhysicsTime>()
+            .init_resource::<CollisionGrid>()
+            .init_resource::<Steps>()
+            .init_resource::<Score>()
+            .init_resource::<SaveData>()
+            .init_resource::<LevelHandles>()
+            .add_event::<CollisionEvent>()
+            .add_event::<LevelEnd>()
+            .add_stage_after(CoreStage::PreUpdate, PhysicsStage, SystemStage::parallel())
+            .add_system_to_stage(CoreStage::PreUpdate, advance_frame)
+            .add_system_to_stage(CoreStage::PreUpdate, restore_physics_position)
+            .add_system_set_to_stage(PhysicsStage, physics_systems().with_system(scripted_input.before(GameSystem::Input)))
+            .add_system_to_stage(CoreStage::PostUpdate, interpolate);
+        let ground = Hitbox {
+            size: Vec2::splat(Tile::SIZE),
+            layers: CollisionLayers::GROUND,
+            ..Default::default()
+        };
+        for i in -5..60 {
+            app.world.spawn()
+                .insert(ground.clone())
+                .insert(Transform::from_translation(Vec3::new(i as f32 * Tile::SIZE, 0.0, 0.0)));
+        }
+        let position = Vec3::new(0.0, Tile::SIZE, 0.0);
+        app.world.spawn()
+            .insert_bundle(PlayerBundle {
+                hitbox: Hitbox {
+                    size: Vec2::splat(Tile::SIZE),
+                    layers: CollisionLayers::PLAYER,
+                    mask: CollisionLayers::GROUND,
+                    ..Default::default()
+                },
+                interpolation: Interpolation::new(position),
+                ..Default::default()
+            })
+            .insert(Transform::from_translation(position));
+        app
+    }
+
+    // rendered position every sixth of a second, the first point where frames at 30, 60 and 144 FPS all line up
+    fn trajectory(fps: u32) -> Vec<Vec3> {
+        let mut app = physics_app(1.0 / fps as f32);
+        let mut points = Vec::new();
+        for frame in 1..=2 * fps {
+            app.update();
+            if frame % (fps / 6) == 0 {
+                let mut query = app.world.query_filtered::<&Transform, With<PlayerCharacter>>();
+                points.push(query.single(&app.world).translation);
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn trajectory_is_independent_of_frame_rate() {
+        let expected = trajectory(60);
+        assert_eq!(expected.len(), 12);
+        // it ran, jumped, landed and slowed down
+        assert!(expected.iter().any(|point| point.y > 2.0 * Tile::SIZE));
+        assert!((expected.last().unwrap().y - Tile::SIZE).abs() < 0.01);
+        assert!(expected[11].x - expected[10].x < expected[5].x - expected[4].x);
+        for fps in [30, 144] {
+            for (point, expected) in trajectory(fps).iter().zip(expected.iter()) {
+                assert!(point.distance(*expected) < 0.01, "{} FPS: {} != {}", fps, point, expected);
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Counts {
+        entities: usize,
+        level: usize,
+        hud: usize,
+        cameras: usize,
+    }
+
+    fn counts(world: &mut World) -> Counts {
+        Counts {
+            entities: world.query::<Entity>().iter(world).count(),
+            level: world.query_filtered::<(), With<LevelEntity>>().iter(world).count(),
+            hud: world.query_filtered::<(), Or<(With<HeartIcon>, With<ScoreText>)>>().iter(world).count(),
+            cameras: world.query_filtered::<(), With<MainCamera>>().iter(world).count(),
+        }
+    }
+
+    // what loading leaves behind: every sprite sheet built, the first level and a camera
+    fn loaded(world: &mut World) {
+        let sprites = SpriteManifest::parse(include_bytes!("../../../assets/default.sprites.ron")).unwrap();
+        let mut sprite_handles = SpriteHandles::default();
+        world.resource_scope(|world, mut textures: Mut<Assets<Image>>| {
+            world.resource_scope(|world, mut texture_atlases: Mut<Assets<TextureAtlas>>| {
+                let mut animations = world.get_resource_mut::<Assets<Animation>>().unwrap();
+                for sprite in SpriteType::ALL {
+                    let handles = sprites.images(sprite).iter().map(|_| textures.add(Image::default())).collect();
+                    sprite_handles.handles.insert(sprite, handles);
+                    sprite_handles.build_sheet(sprite, &sprites, &mut texture_atlases, &mut textures, &mut animations);
+                }
+            });
+        });
+        let map = Map::parse(Path::new("level_0.level"), include_str!("../../../assets/levels/level_0.level")).unwrap();
+        let map = world.get_resource_mut::<Assets<Map>>().unwrap().add(map);
+        world.insert_resource(sprites);
+        world.insert_resource(sprite_handles);
+        world.insert_resource(LevelHandles { handles: vec![map], current: 0 });
+        world.spawn().insert_bundle(OrthographicCameraBundle::new_2d()).insert(MainCamera);
+        world.spawn().insert_bundle(UiCameraBundle::default());
+    }
+
+    // the real plugins, headless: no window, no renderer and what loading would have left behind
+    fn app() -> App {
+        let mut app = App::new();
+        app
+            .add_plugins(MinimalPlugins)
+            .add_plugin(AssetPlugin)
+            .add_plugin(InputPlugin)
+            .add_asset::<Image>()
+            .add_asset::<TextureAtlas>()
+            .init_resource::<Windows>()
+            .init_resource::<Bindings>()
+            .init_resource::<SaveData>()
+            .add_plugin(SpritePlugin)
+            .add_plugin(AnimationPlugin)
+            .add_plugin(ControlsPlugin)
+            .add_plugin(MenuPlugin)
+            .add_state(AppState::Game)
+            .add_plugin(Game)
+            .add_plugin(GameOver);
+        loaded(&mut app.world);
+        app
+    }
+
+    fn set_state(app: &mut App, state: AppState) {
+        app.world.get_resource_mut::<State<AppState>>().unwrap().set(state).unwrap();
+        app.update();
+    }
+
+    #[test]
+    fn restarting_rebuilds_the_same_level() {
+        let mut app = app();
+        app.update();
+        let expected = counts(&mut app.world);
+        assert!(expected.level > 0);
+        assert!(expected.hud > 0);
+        assert_eq!(expected.cameras, 1);
+
+        for _ in 0..5 {
+            let mut query = app.world.query_filtered::<&mut Transform, With<MainCamera>>();
+            query.single_mut(&mut app.world).translation.x = 500.0;
+            set_state(&mut app, AppState::GameOver);
+            // the game over text and restart button
+            assert!(counts(&mut app.world).entities > expected.entities);
+            set_state(&mut app, AppState::Game);
+            assert_eq!(counts(&mut app.world), expected);
+            let mut query = app.world.query_filtered::<&Transform, With<MainCamera>>();
+            assert_eq!(query.single(&app.world).translation.x, 0.0);
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+
+use crate::controls::{Action, ActionState, Controls};
+use crate::sprite::SpriteType;
+
+use super::hitbox::Hitbox;
+use super::level::LevelEntity;
+
+#[derive(Component)]
+pub struct Npc {
+    pub pages: &'static [&'static str],
+}
+
+impl Npc {
+    pub fn new(sprite_type: SpriteType) -> Self {
+        let pages: &'static [&'static str] = match sprite_type {
+            SpriteType::Jeremy => &[
+                "Oh, hello there!",
+                "I'm Jeremy. Have you seen my kid around?",
+                "He keeps wandering off to the right...",
+            ],
+            SpriteType::BabyJeremy => &[
+                "Goo goo!",
+            ],
+            SpriteType::Angel => &[
+                "Be careful out there.",
+                "You can jump again while in the air.",
+                "And you can stomp the blue ones!",
+            ],
+            SpriteType::Hedgehog => &[
+                "...",
+                "The hedgehog stares at you.",
+            ],
+            _ => &[
+                "Hi!",
+            ],
+        };
+        Npc { pages }
+    }
+}
+
+#[derive(Component)]
+pub struct DialogueBox {
+    pages: &'static [&'static str],
+    page: usize,
+}
+
+#[derive(Component)]
+pub struct DialogueText;
+
+fn spawn_dialogue_box(commands: &mut Commands, asset_server: &AssetServer, pages: &'static [&'static str]) {
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: Rect {
+                    left: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    bottom: Val::Px(10.0),
+                    ..Default::default()
+                },
+                size: Size::new(Val::Auto, Val::Px(100.0)),
+                padding: Rect::all(Val::Px(10.0)),
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.7).into(),
+            ..Default::default()
+        })
+        .insert(DialogueBox { pages, page: 0 })
+        .insert(LevelEntity)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        pages[0],
+                        TextStyle {
+                            font: asset_server.load("kenney-fonts/Fonts/Kenney Pixel.ttf"),
+                            font_size: 32.0,
+                            color: Color::rgb(0.9, 0.9, 0.9),
+                        },
+                        Default::default(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(DialogueText);
+        });
+}
+
+pub fn interact(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    actions: Res<ActionState>,
+    npc_query: Query<(&Npc, &Hitbox, &Transform)>,
+    player_query: Query<(&Controls, &Hitbox, &Transform)>,
+    mut dialogue_query: Query<(Entity, &mut DialogueBox)>,
+    mut text_query: Query<&mut Text, With<DialogueText>>,
+) {
+    // checked every frame by overlap instead of through collision events, since a frame can run without any physics step,
+    // which is why players don't have the npc layer in their mask
+    let mut in_range = false;
+    let mut pressed_npc = None; // next to a player pressing interact
+    for (&controls, hitbox, transform) in player_query.iter() {
+        let npc_in_range = npc_query.iter()
+            .find(|(_, npc_hitbox, npc_transform)| hitbox.overlaps(&transform.translation, npc_hitbox, &npc_transform.translation))
+            .map(|(npc, _, _)| npc);
+        if let Some(npc) = npc_in_range {
+            in_range = true;
+            if actions.just_pressed(controls, Action::Interact) {
+                pressed_npc.get_or_insert(npc);
+            }
+        }
+    }
+    // the dialogue stays open as long as any player is next to an npc
+    match (dialogue_query.get_single_mut(), in_range, pressed_npc) {
+        (Ok((id, _)), false, _) => commands.entity(id).despawn_recursive(),
+        (Ok((id, mut dialogue)), true, Some(_)) => {
+            dialogue.page += 1;
+            if dialogue.page < dialogue.pages.len() {
+                for mut text in text_query.iter_mut() {
+                    text.sections[0].value = dialogue.pages[dialogue.page].to_string();
+                }
+            } else {
+                commands.entity(id).despawn_recursive();
+            }
+        },
+        (Err(_), _, Some(npc)) => spawn_dialogue_box(&mut commands, &asset_server, npc.pages),
+        _ => {},
+    }
+}
@@ -0,0 +1,132 @@
+use bevy::ecs::schedule::ShouldRun;
+use bevy::prelude::*;
+
+use crate::state::AppState;
+
+pub const PHYSICS_STEP: f32 = 1.0 / 60.0;
+// a single long frame (window dragged, tab in the background) shouldn't queue up hundreds of catch-up steps
+const MAX_ACCUMULATED: f32 = 0.25;
+
+#[derive(StageLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PhysicsStage;
+
+#[derive(Default)]
+pub struct PhysicsTime {
+    accumulator: f32,
+}
+
+impl PhysicsTime {
+    // how far the simulation is between the last step and the next one, used to interpolate what gets rendered
+    pub fn alpha(&self) -> f32 {
+        self.accumulator / PHYSICS_STEP
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        self.accumulator = (self.accumulator + delta).min(MAX_ACCUMULATED);
+    }
+}
+
+// The transform of a moving entity is its physics position while the physics stage runs and the interpolated
+// position everywhere else, so it gets restored before the stage and interpolated after it
+#[derive(Component, Default)]
+pub struct Interpolation {
+    previous: Vec3,
+    current: Vec3,
+}
+
+impl Interpolation {
+    pub fn new(position: Vec3) -> Self {
+        Interpolation {
+            previous: position,
+            current: position,
+        }
+    }
+}
+
+pub fn in_game(state: Res<State<AppState>>) -> ShouldRun {
+    if state.current() == &AppState::Game {
+        ShouldRun::Yes
+    } else {
+        ShouldRun::No
+    }
+}
+
+pub fn accumulate_physics_time(time: Res<Time>, mut physics_time: ResMut<PhysicsTime>) {
+    physics_time.advance(time.delta_seconds());
+}
+
+// runs the physics stage once for every step that fits in the time accumulated so far
+pub fn physics_step(state: Res<State<AppState>>, mut physics_time: ResMut<PhysicsTime>) -> ShouldRun {
+    if state.current() != &AppState::Game {
+        return ShouldRun::No;
+    }
+    if physics_time.accumulator >= PHYSICS_STEP {
+        physics_time.accumulator -= PHYSICS_STEP;
+        ShouldRun::YesAndCheckAgain
+    } else {
+        ShouldRun::No
+    }
+}
+
+pub fn restore_physics_position(mut query: Query<(&Interpolation, &mut Transform)>) {
+    for (interpolation, mut transform) in query.iter_mut() {
+        transform.translation = interpolation.current;
+    }
+}
+
+pub fn begin_step(mut query: Query<(&mut Interpolation, &Transform)>) {
+    for (mut interpolation, transform) in query.iter_mut() {
+        interpolation.previous = transform.translation;
+    }
+}
+
+pub fn end_step(mut query: Query<(&mut Interpolation, &Transform)>) {
+    for (mut interpolation, transform) in query.iter_mut() {
+        interpolation.current = transform.translation;
+    }
+}
+
+pub fn interpolate(
+    physics_time: Res<PhysicsTime>,
+    mut query: Query<(&Interpolation, &mut Transform)>,
+) {
+    let alpha = physics_time.alpha();
+    for (interpolation, mut transform) in query.iter_mut() {
+        transform.translation = interpolation.previous.lerp(interpolation.current, alpha);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // stands in for Time, whose delta can't be set from outside bevy
+    struct FrameTime(f32);
+
+    #[derive(Default)]
+    struct Steps(usize);
+
+    fn advance_frame(frame_time: Res<FrameTime>, mut physics_time: ResMut<PhysicsTime>) {
+        physics_time.advance(frame_time.0);
+    }
+
+    fn count_step(mut steps: ResMut<Steps>) {
+        steps.0 += 1;
+    }
+
+    #[test]
+    fn long_frame_is_capped() {
+        let mut app = App::new();
+        app
+            .insert_resource(State::new(AppState::Game))
+            .insert_resource(FrameTime(10.0))
+            .init_resource::<PhysicsTime>()
+            .init_resource::<Steps>()
+            .add_stage_after(CoreStage::PreUpdate, PhysicsStage, SystemStage::parallel())
+            .add_system_to_stage(CoreStage::PreUpdate, advance_frame)
+            .add_system_set_to_stage(PhysicsStage, SystemSet::new().with_run_criteria(physics_step).with_system(count_step));
+        app.update();
+        let steps = app.world.get_resource::<Steps>().unwrap().0;
+        assert!(steps > 0 && steps <= (MAX_ACCUMULATED / PHYSICS_STEP).ceil() as usize, "{} steps", steps);
+    }
+}
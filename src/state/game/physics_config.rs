@@ -0,0 +1,65 @@
+use bevy::asset::{AssetLoader, HandleId, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+use bevy::utils::BoxedFuture;
+use serde::Deserialize;
+
+const PHYSICS_CONFIG: &str = "default.physics.ron";
+
+// Speeds in pixels per second, accelerations in pixels per second squared
+#[derive(Clone, Deserialize, TypeUuid)]
+#[uuid = "0f5a3d38-7c1e-4b2a-9d61-3e8f2c4b7a15"]
+pub struct PhysicsConfig {
+    pub gravity: f32,
+    pub max_fall_speed: f32,
+    pub max_run_speed: f32,
+    pub acceleration: f32,
+    pub friction: f32, // fraction of the horizontal speed kept every 60th of a second when not running
+    pub jump_speed: f32,
+    pub jump_cut: f32, // fraction of the upward speed kept when jump is released early
+    pub max_jump_count: u8,
+    pub coyote_time: f32, // seconds after walking off a ledge in which jumping still counts as a ground jump
+    pub jump_buffer_time: f32, // seconds a jump pressed before landing is remembered
+    pub wall_slide_speed: f32, // max fall speed while pressing into a wall
+    pub wall_jump_speed: Vec2, // horizontal speed away from the wall and vertical speed up
+}
+
+// the shipped config, used until the asset loads
+impl Default for PhysicsConfig {
+    fn default() -> Self {
+        ron::de::from_bytes(include_bytes!("../../../assets/default.physics.ron")).unwrap()
+    }
+}
+
+#[derive(Default)]
+pub struct PhysicsConfigHandle(Handle<PhysicsConfig>);
+
+impl PhysicsConfigHandle {
+    pub fn load(&mut self, asset_server: &AssetServer) {
+        self.0 = asset_server.load(PHYSICS_CONFIG);
+    }
+    pub fn id(&self) -> HandleId {
+        self.0.id
+    }
+}
+
+#[derive(Default)]
+pub struct PhysicsConfigLoader;
+
+impl AssetLoader for PhysicsConfigLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+        Box::pin(async move {
+            let config: PhysicsConfig = ron::de::from_bytes(bytes)?;
+            load_context.set_default_asset(LoadedAsset::new(config));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["physics.ron"]
+    }
+}
@@ -4,17 +4,31 @@ use crate::controls::Controls;
 use crate::sprite::SpriteType;
 
 use super::direction::Direction;
-use super::hitbox::{PlayerGroundHitbox, PlayerEnemyHitbox};
+use super::health::Health;
+use super::hitbox::{GroundContacts, Hitbox};
+use super::physics::Interpolation;
+use super::physics_config::PhysicsConfig;
 use super::velocity::Velocity;
 
 #[derive(Bundle, Default)]
 pub struct PlayerBundle {
     pub player_character: PlayerCharacter,
     pub controls: Controls,
-    pub ground_hitbox: PlayerGroundHitbox,
-    pub enemy_hitbox: PlayerEnemyHitbox,
+    pub hitbox: Hitbox,
+    pub ground_contacts: GroundContacts,
     pub velocity: Velocity,
     pub direction: Direction,
+    pub health: Health,
+    pub player_input: PlayerInput,
+    pub interpolation: Interpolation,
+}
+
+// input is read every frame but consumed by the physics steps, so a jump pressed between two steps isn't lost or repeated
+#[derive(Component, Default)]
+pub struct PlayerInput {
+    pub direction: Option<Direction>,
+    pub jump_pressed: bool,
+    pub jump_released: bool,
 }
 
 #[derive(Clone, Copy, Default, PartialEq)]
@@ -23,25 +37,80 @@ enum State {
     Idle,
     Walking,
     InTheAir(u8),
+    WallSliding(Direction), // side the wall is on
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum Character {
+    #[default]
+    Green,
+    Pink,
+    Yellow,
+    Beige,
 }
 
+impl Character {
+    pub fn for_player(player: usize) -> Self {
+        match player % 4 {
+            0 => Character::Green,
+            1 => Character::Pink,
+            2 => Character::Yellow,
+            _ => Character::Beige,
+        }
+    }
+    pub fn idle(self) -> SpriteType {
+        self.spritesheet(State::Idle)
+    }
+    //TODO: the other colours only have a walking animation
+    fn spritesheet(self, state: State) -> SpriteType {
+        match (self, state) {
+            (Character::Green, State::Idle) => SpriteType::IdleGreen,
+            (Character::Green, State::Walking) => SpriteType::WalkGreen,
+            (Character::Green, State::InTheAir(_) | State::WallSliding(_)) => SpriteType::JumpGreen,
+            (Character::Pink, _) => SpriteType::Pink,
+            (Character::Yellow, _) => SpriteType::Yellow,
+            (Character::Beige, _) => SpriteType::Beige,
+        }
+    }
+}
+
+pub enum Jump {
+    Normal,
+    Wall(Direction), // direction to kick off to
+}
+
+// InTheAir(0) is falling without having jumped, e.g. after walking off a ledge
 #[derive(Component, Default)]
 pub struct PlayerCharacter {
+    character: Character,
     state: State,
     previous_state: State,
+    coyote_time: f32, // time left to jump as if still on the ground
+    jump_buffer: f32, // time left for a jump pressed too early to happen anyway
+    can_wall_jump: bool,
 }
 
 impl PlayerCharacter {
-    const MAX_JUMP_COUNT: u8 = 2;
+    pub fn new(character: Character, can_wall_jump: bool) -> Self {
+        PlayerCharacter {
+            character,
+            can_wall_jump,
+            ..Default::default()
+        }
+    }
     fn get_jump_count(&self) -> u8 {
-        if let State::InTheAir(jump_count) = self.state {
-            jump_count
-        } else {
-            0
+        match self.state {
+            State::InTheAir(0) if self.coyote_time <= 0.0 => 1,
+            State::InTheAir(jump_count) => jump_count,
+            _ => 0,
         }
     }
+    pub fn tick(&mut self, time: f32) {
+        self.coyote_time = (self.coyote_time - time).max(0.0);
+        self.jump_buffer = (self.jump_buffer - time).max(0.0);
+    }
     pub fn update_walk_state(&mut self, x_velocity: f32) {
-        if let State::InTheAir(_) = self.state {
+        if let State::InTheAir(_) | State::WallSliding(_) = self.state {
             return;
         }
         self.state = if x_velocity != 0.0 {
@@ -50,18 +119,51 @@ impl PlayerCharacter {
                 State::Idle
             };
     }
-    pub fn try_jump(&mut self) -> Result<(), ()> {
-        let jump_count = self.get_jump_count();
-        if jump_count < Self::MAX_JUMP_COUNT {
-            self.state = State::InTheAir(jump_count+1);
-            Ok(())
-        } else {
-            Err(())
+    pub fn press_jump(&mut self, config: &PhysicsConfig) {
+        self.jump_buffer = config.jump_buffer_time;
+    }
+    // jumps if a jump was pressed recently and there are jumps left, otherwise keeps it buffered
+    pub fn try_jump(&mut self, config: &PhysicsConfig) -> Result<Jump, ()> {
+        if self.jump_buffer <= 0.0 {
+            return Err(());
         }
+        let jump = if let State::WallSliding(wall) = self.state {
+            self.state = State::InTheAir(1);
+            Jump::Wall(wall.opposite())
+        } else {
+            let jump_count = self.get_jump_count();
+            if jump_count >= config.max_jump_count {
+                return Err(());
+            }
+            self.state = State::InTheAir(jump_count+1);
+            Jump::Normal
+        };
+        self.coyote_time = 0.0;
+        self.jump_buffer = 0.0;
+        Ok(jump)
     }
-    pub fn hit_ground(&mut self) {
-        if let State::InTheAir(_) = self.state {
-            self.state = State::Idle;
+    pub fn is_jumping(&self) -> bool {
+        matches!(self.state, State::InTheAir(jump_count) if jump_count > 0)
+    }
+    pub fn is_wall_sliding(&self) -> bool {
+        matches!(self.state, State::WallSliding(_))
+    }
+    pub fn update_contacts(&mut self, contacts: &GroundContacts, pressing: Option<Direction>, falling: bool, config: &PhysicsConfig) {
+        let wall = match pressing {
+            Some(Direction::Left) if contacts.left => Some(Direction::Left),
+            Some(Direction::Right) if contacts.right => Some(Direction::Right),
+            _ => None,
+        };
+        match (self.state, contacts.bottom, wall) {
+            (State::InTheAir(_) | State::WallSliding(_), true, _) => self.state = State::Idle,
+            (State::Idle | State::Walking, false, _) => {
+                self.state = State::InTheAir(0);
+                self.coyote_time = config.coyote_time;
+            },
+            (State::InTheAir(_), false, Some(wall)) if self.can_wall_jump && falling => self.state = State::WallSliding(wall),
+            // sliding down a wall gives back the air jumps
+            (State::WallSliding(_), false, None) => self.state = State::InTheAir(1),
+            _ => {},
         }
     }
     pub fn update_spritesheet(&mut self) -> Option<SpriteType> {
@@ -69,11 +171,128 @@ impl PlayerCharacter {
             return None;
         }
         self.previous_state = self.state;
-        let spritesheet = match self.state {
-            State::Idle => SpriteType::IdleGreen,
-            State::Walking => SpriteType::WalkGreen,
-            State::InTheAir(_) => SpriteType::JumpGreen,
-        };
-        Some(spritesheet)
+        Some(self.character.spritesheet(self.state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: f32 = 1.0 / 60.0;
+
+    fn on_ground() -> GroundContacts {
+        GroundContacts { bottom: true, ..Default::default() }
+    }
+
+    fn in_the_air() -> GroundContacts {
+        GroundContacts::default()
+    }
+
+    fn against_right_wall() -> GroundContacts {
+        GroundContacts { right: true, ..Default::default() }
+    }
+
+    fn jump(player: &mut PlayerCharacter, config: &PhysicsConfig) -> Result<Jump, ()> {
+        player.press_jump(config);
+        player.try_jump(config)
+    }
+
+    #[test]
+    fn coyote_time_allows_a_ground_jump() {
+        let config = PhysicsConfig::default();
+        let mut player = PlayerCharacter::new(Character::Green, false);
+        player.update_contacts(&in_the_air(), None, true, &config);
+        player.tick(config.coyote_time / 2.0);
+        assert!(matches!(jump(&mut player, &config), Ok(Jump::Normal)));
+        // the air jump is still left
+        assert!(jump(&mut player, &config).is_ok());
+        assert!(jump(&mut player, &config).is_err());
+    }
+
+    #[test]
+    fn walking_off_a_ledge_uses_a_jump_after_coyote_time() {
+        let config = PhysicsConfig::default();
+        let mut player = PlayerCharacter::new(Character::Green, false);
+        player.update_contacts(&in_the_air(), None, true, &config);
+        player.tick(config.coyote_time + STEP);
+        assert!(jump(&mut player, &config).is_ok());
+        assert!(jump(&mut player, &config).is_err());
+    }
+
+    #[test]
+    fn jump_pressed_before_landing_happens_on_landing() {
+        let config = PhysicsConfig::default();
+        let mut player = PlayerCharacter::new(Character::Green, false);
+        jump(&mut player, &config).unwrap();
+        jump(&mut player, &config).unwrap();
+        assert!(jump(&mut player, &config).is_err());
+        player.tick(config.jump_buffer_time / 2.0);
+        player.update_contacts(&on_ground(), None, false, &config);
+        assert!(matches!(player.try_jump(&config), Ok(Jump::Normal)));
+        assert!(player.is_jumping());
+    }
+
+    #[test]
+    fn buffered_jump_expires() {
+        let config = PhysicsConfig::default();
+        let mut player = PlayerCharacter::new(Character::Green, false);
+        jump(&mut player, &config).unwrap();
+        jump(&mut player, &config).unwrap();
+        player.press_jump(&config);
+        player.tick(config.jump_buffer_time + STEP);
+        player.update_contacts(&on_ground(), None, false, &config);
+        assert!(player.try_jump(&config).is_err());
+        assert!(!player.is_jumping());
+    }
+
+    #[test]
+    fn jumps_are_limited_by_max_jump_count() {
+        for max_jump_count in 1..=3 {
+            let config = PhysicsConfig { max_jump_count, ..Default::default() };
+            let mut player = PlayerCharacter::new(Character::Green, false);
+            for _ in 0..max_jump_count {
+                assert!(jump(&mut player, &config).is_ok());
+            }
+            assert!(jump(&mut player, &config).is_err());
+        }
+    }
+
+    #[test]
+    fn wall_slide_then_wall_jump() {
+        let config = PhysicsConfig::default();
+        let mut player = PlayerCharacter::new(Character::Green, true);
+        jump(&mut player, &config).unwrap();
+        jump(&mut player, &config).unwrap();
+        player.update_contacts(&against_right_wall(), Some(Direction::Right), true, &config);
+        assert!(player.is_wall_sliding());
+        assert!(matches!(jump(&mut player, &config), Ok(Jump::Wall(Direction::Left))));
+        assert!(!player.is_wall_sliding());
+        // kicking off the wall gives back the air jump
+        assert!(jump(&mut player, &config).is_ok());
+        assert!(jump(&mut player, &config).is_err());
+    }
+
+    #[test]
+    fn letting_go_of_the_wall_stops_sliding() {
+        let config = PhysicsConfig::default();
+        let mut player = PlayerCharacter::new(Character::Green, true);
+        jump(&mut player, &config).unwrap();
+        player.update_contacts(&against_right_wall(), Some(Direction::Right), true, &config);
+        assert!(player.is_wall_sliding());
+        player.update_contacts(&in_the_air(), None, true, &config);
+        assert!(!player.is_wall_sliding());
+        assert!(player.is_jumping());
+    }
+
+    #[test]
+    fn no_wall_slide_without_wall_jump() {
+        let config = PhysicsConfig::default();
+        let mut player = PlayerCharacter::new(Character::Green, false);
+        jump(&mut player, &config).unwrap();
+        player.update_contacts(&against_right_wall(), Some(Direction::Right), true, &config);
+        assert!(!player.is_wall_sliding());
+        assert!(matches!(jump(&mut player, &config), Ok(Jump::Normal)));
+        assert!(jump(&mut player, &config).is_err());
     }
 }
@@ -1,34 +1,32 @@
 use bevy::prelude::*;
 
 use super::direction;
+use super::physics_config::PhysicsConfig;
 
 #[derive(Component, Default)]
 pub struct Velocity(pub Vec3);
 
 impl Velocity {
-    pub fn apply_gravity(&mut self, time: f32) {
-        let max_speed = 300.0;
-        let gravity_acceleration = 500.0;
-        self.0.y -= gravity_acceleration * time;
-        limit(&mut self.0.y, max_speed);
-    }
-    fn increase(&mut self, direction: direction::Direction) {
-        let max_speed = 250.0;
-        let speed_increase = 10.0;
-        self.0.x += speed_increase * f32::from(direction);
-        limit(&mut self.0.x, max_speed);
-    }
-    fn decrease(&mut self) {
+    pub fn apply_gravity(&mut self, config: &PhysicsConfig, time: f32) {
+        self.0.y -= config.gravity * time;
+        limit(&mut self.0.y, config.max_fall_speed);
+    }
+    fn increase(&mut self, config: &PhysicsConfig, direction: direction::Direction, time: f32) {
+        self.0.x += config.acceleration * time * f32::from(direction);
+        limit(&mut self.0.x, config.max_run_speed);
+    }
+    fn decrease(&mut self, config: &PhysicsConfig, time: f32) {
+        let friction = config.friction.powf(time * 60.0);
         if self.0.x.abs() < 10.0 {
             self.0.x = 0.0
         } else {
-            self.0.x *= 0.9
+            self.0.x *= friction
         };
     }
-    pub fn update(&mut self, direction: Option<direction::Direction>) {
+    pub fn update(&mut self, config: &PhysicsConfig, direction: Option<direction::Direction>, time: f32) {
         match direction {
-            None => self.decrease(),
-            Some(dir) => self.increase(dir),
+            None => self.decrease(config, time),
+            Some(dir) => self.increase(config, dir, time),
         }
     }
     pub fn stop_left(&mut self) {
@@ -1,16 +1,23 @@
 use bevy::prelude::*;
 use crate::camera::MainCamera;
+use crate::menu::{spawn_button, ButtonPressed, MenuButton, MenuSystem};
 
-use crate::state::AppState;
+use crate::state::{AppState, Score};
 
 pub struct GameOver;
 
+#[derive(Component)]
+struct GameOverEntity;
+
+#[derive(Component)]
+struct RestartButton;
+
 impl Plugin for GameOver {
     fn build(&self, app: &mut App) {
         app
             .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(show_text))
             .add_system_set(SystemSet::on_enter(AppState::GameOver).with_system(show_button))
-            .add_system_set(SystemSet::on_update(AppState::GameOver).with_system(button))
+            .add_system_set(SystemSet::on_update(AppState::GameOver).with_system(button.after(MenuSystem)))
             .add_system_set(SystemSet::on_exit(AppState::GameOver).with_system(cleanup));
     }
 }
@@ -18,6 +25,7 @@ impl Plugin for GameOver {
 fn show_text(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    score: Res<Score>,
     camera_query: Query<(&MainCamera, &Transform)>,
 ) {
     let camera_position = camera_query.single().1.translation;
@@ -35,63 +43,66 @@ fn show_text(
             text: Text::with_section("Game\nOver", text_style, text_alignment),
             transform: Transform::from_translation(Vec3::new(camera_position.x, camera_position.y + 150.0, 10.0)),
             ..Default::default()
-        });
+        })
+        .insert(GameOverEntity);
+    let score_style = TextStyle {
+        font: asset_server.load("kenney-fonts/Fonts/Kenney Pixel.ttf"),
+        font_size: 48.0,
+        color: Color::GOLD,
+    };
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(format!("Score: {}", score.0), score_style, text_alignment),
+            transform: Transform::from_translation(Vec3::new(camera_position.x, camera_position.y + 50.0, 10.0)),
+            ..Default::default()
+        })
+        .insert(GameOverEntity);
 }
 
 fn show_button(
     mut commands: Commands,
-    asset_server: Res<AssetServer>
+    asset_server: Res<AssetServer>,
 ) {
-    commands.spawn_bundle(UiCameraBundle::default()); //TODO: spawn this during pre-load
+    let text_style = TextStyle {
+        font: asset_server.load("kenney-fonts/Fonts/Kenney Pixel.ttf"),
+        font_size: 40.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
     commands
-        .spawn_bundle(ButtonBundle {
+        .spawn_bundle(NodeBundle {
             style: Style {
-                size: Size::new(Val::Px(150.0), Val::Px(65.0)),
-                margin: Rect::all(Val::Auto),
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
                 ..Default::default()
             },
+            color: Color::NONE.into(),
             ..Default::default()
         })
+        .insert(GameOverEntity)
         .with_children(|parent| {
-            parent.spawn_bundle(TextBundle {
-                text: Text::with_section(
-                    "Restart",
-                    TextStyle {
-                        font: asset_server.load("kenney-fonts/Fonts/Kenney Pixel.ttf"),
-                        font_size: 40.0,
-                        color: Color::rgb(0.9, 0.9, 0.9),
-                    },
-                    Default::default(),
-                ),
-                ..Default::default()
-            });
+            spawn_button(parent, "Restart", text_style)
+                .insert(MenuButton(0))
+                .insert(RestartButton);
         });
 }
 
-
 fn button(
     mut state: ResMut<State<AppState>>,
-    mut query: Query<(&Interaction, &mut UiColor), (Changed<Interaction>, With<Button>)>,
+    mut events: EventReader<ButtonPressed>,
+    query: Query<(), With<RestartButton>>,
 ) {
-    for (interaction, mut color) in query.iter_mut() {
-        *color = match *interaction {
-            Interaction::Hovered => Color::DARK_GRAY.into(),
-            Interaction::None => Color::rgb(0.15, 0.15, 0.15).into(),
-            Interaction::Clicked => {
-                state.set(AppState::Game).unwrap();
-                Color::DARK_GRAY.into()
-            },
-        }
+    if events.iter().any(|event| query.get(event.0).is_ok()) {
+        state.set(AppState::Game).unwrap();
     }
 }
 
 fn cleanup(
     mut commands: Commands,
-    query: Query<Entity, Without<OrthographicProjection>>,
+    query: Query<Entity, With<GameOverEntity>>,
 ) {
     for id in query.iter() {
-        commands.entity(id).despawn();
+        commands.entity(id).despawn_recursive();
     }
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+use bevy::prelude::*;
+use crate::camera::MainCamera;
+use crate::menu::{spawn_button, ButtonPressed, MenuButton, MenuSystem};
+use crate::save::SaveData;
+
+use crate::state::{AppState, LevelHandles, LevelTime};
+
+pub struct LevelComplete;
+
+#[derive(Component)]
+struct LevelCompleteEntity;
+
+#[derive(Component)]
+struct NextLevelButton;
+
+impl Plugin for LevelComplete {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(SystemSet::on_enter(AppState::LevelComplete).with_system(save_progress))
+            .add_system_set(SystemSet::on_enter(AppState::LevelComplete).with_system(show_text))
+            .add_system_set(SystemSet::on_enter(AppState::LevelComplete).with_system(show_button))
+            .add_system_set(SystemSet::on_update(AppState::LevelComplete).with_system(button.after(MenuSystem)))
+            .add_system_set(SystemSet::on_exit(AppState::LevelComplete).with_system(cleanup));
+    }
+}
+
+fn save_progress(
+    level_handles: Res<LevelHandles>,
+    level_time: Res<LevelTime>,
+    mut save: ResMut<SaveData>,
+) {
+    let next = if level_handles.is_last() { 0 } else { level_handles.current + 1 };
+    save.complete_level(level_handles.current, next, level_time.0);
+}
+
+fn show_text(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level_handles: Res<LevelHandles>,
+    camera_query: Query<(&MainCamera, &Transform)>,
+) {
+    let camera_position = camera_query.single().1.translation;
+    let text = if level_handles.is_last() { "You\nWin" } else { "Level\nComplete" };
+    let text_style = TextStyle {
+        font: asset_server.load("kenney-fonts/Fonts/Kenney Blocks.ttf"),
+        font_size: 96.0,
+        color: Color::GOLD,
+    };
+    let text_alignment = TextAlignment {
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Center,
+    };
+    commands
+        .spawn_bundle(Text2dBundle {
+            text: Text::with_section(text, text_style, text_alignment),
+            transform: Transform::from_translation(Vec3::new(camera_position.x, camera_position.y + 150.0, 10.0)),
+            ..Default::default()
+        })
+        .insert(LevelCompleteEntity);
+}
+
+fn show_button(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level_handles: Res<LevelHandles>,
+) {
+    let text = if level_handles.is_last() { "Play Again" } else { "Next Level" };
+    let text_style = TextStyle {
+        font: asset_server.load("kenney-fonts/Fonts/Kenney Pixel.ttf"),
+        font_size: 40.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(LevelCompleteEntity)
+        .with_children(|parent| {
+            spawn_button(parent, text, text_style)
+                .insert(MenuButton(0))
+                .insert(NextLevelButton);
+        });
+}
+
+fn button(
+    mut state: ResMut<State<AppState>>,
+    mut level_handles: ResMut<LevelHandles>,
+    mut events: EventReader<ButtonPressed>,
+    query: Query<(), With<NextLevelButton>>,
+) {
+    if events.iter().any(|event| query.get(event.0).is_ok()) {
+        level_handles.advance();
+        state.set(AppState::Game).unwrap();
+    }
+}
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<LevelCompleteEntity>>,
+) {
+    for id in query.iter() {
+        commands.entity(id).despawn_recursive();
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::sprite::{SPRITES, SpriteType, SpriteTypeStates};
+use crate::sprite::SpriteTypeStates;
 
 #[derive(Component)]
 pub struct LoadingBar {
@@ -9,13 +9,13 @@ pub struct LoadingBar {
 }
 
 impl LoadingBar {
-    pub fn get_image(&self, percent: f32) -> &str {
+    pub fn get_image(&self, percent: f32) -> SpriteTypeStates {
         if percent < self.lower_bound {
-            SPRITES[&SpriteType::Heart][&SpriteTypeStates::Empty]
+            SpriteTypeStates::Empty
         } else if percent >= self.upper_bound {
-            SPRITES[&SpriteType::Heart][&SpriteTypeStates::Full]
+            SpriteTypeStates::Full
         } else {
-            SPRITES[&SpriteType::Heart][&SpriteTypeStates::Half]
+            SpriteTypeStates::Half
         }
     }
 }
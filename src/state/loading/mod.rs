@@ -1,8 +1,9 @@
 use bevy::asset::LoadState;
 use bevy::prelude::*;
 
+use crate::animation::Animation;
 use crate::camera::MainCamera;
-use crate::state::AppState;
+use crate::state::{AppState, LevelHandles, PhysicsConfigHandle};
 use crate::sprite::*;
 
 mod loading_bar;
@@ -14,26 +15,41 @@ impl Plugin for Loading {
     fn build(&self, app: &mut App) {
         app
             .add_state(AppState::PreLoad)
-            .add_system_set(SystemSet::on_enter(AppState::PreLoad).with_system(load_preloaded_textures))
+            .add_system_set(SystemSet::on_enter(AppState::PreLoad).with_system(load_sprite_manifest))
             .add_system_set(SystemSet::on_update(AppState::PreLoad).with_system(check_preloaded_textures))
             .add_system_set(SystemSet::on_enter(AppState::Loading).with_system(spawn_camera))
             .add_system_set(SystemSet::on_enter(AppState::Loading).with_system(setup_loading_bar))
             .add_system_set(SystemSet::on_enter(AppState::Loading).with_system(load_textures))
+            .add_system_set(SystemSet::on_enter(AppState::Loading).with_system(load_levels))
+            .add_system_set(SystemSet::on_enter(AppState::Loading).with_system(load_physics_config))
             .add_system_set(SystemSet::on_update(AppState::Loading).with_system(check_textures))
+            .add_system_set(SystemSet::on_exit(AppState::Loading).with_system(build_sprite_sheets))
             .add_system_set(SystemSet::on_exit(AppState::Loading).with_system(remove_loading_bar));
     }
 }
 
-fn load_preloaded_textures(mut sprite_handles: ResMut<SpriteHandles>, asset_server: Res<AssetServer>) {
-    let handles = load_sprites(SpriteType::Heart, &asset_server);
-    sprite_handles.handles.insert("loading".to_string(), handles);
+fn load_sprite_manifest(mut sprite_manifest_handle: ResMut<SpriteManifestHandle>, asset_server: Res<AssetServer>) {
+    sprite_manifest_handle.load(&asset_server);
 }
 
 fn check_preloaded_textures(
     mut state: ResMut<State<AppState>>,
-    sprite_handles: ResMut<SpriteHandles>,
+    mut sprite_handles: ResMut<SpriteHandles>,
+    sprites: Res<SpriteManifest>,
+    sprite_manifest_handle: Res<SpriteManifestHandle>,
     asset_server: Res<AssetServer>,
 ) {
+    if let LoadState::Failed = asset_server.get_load_state(sprite_manifest_handle.id()) {
+        panic!("Failed to load the sprite manifest.");
+    }
+    // the loading bar is made of hearts, which can only be loaded once the manifest says where they are
+    if !sprites.is_loaded() {
+        return;
+    }
+    if !sprite_handles.handles.contains_key(&SpriteType::Heart) {
+        let handles = load_sprites(SpriteType::Heart, &sprites, &asset_server);
+        sprite_handles.handles.insert(SpriteType::Heart, handles);
+    }
     let ids = sprite_handles.id_list();
     if let LoadState::Loaded = asset_server.get_group_load_state(ids) {
         state.set(AppState::Loading).unwrap();
@@ -43,20 +59,20 @@ fn check_preloaded_textures(
 fn spawn_camera(mut commands: Commands) {
     commands.spawn_bundle(OrthographicCameraBundle::new_2d())
         .insert(MainCamera);
+    commands.spawn_bundle(UiCameraBundle::default());
 }
 
 fn setup_loading_bar(
     mut commands: Commands,
-    sprite_handles: Res<SpriteHandles>,
+    sprites: Res<SpriteManifest>,
+    mut sprite_handles: ResMut<SpriteHandles>,
     mut texture_atlases: ResMut<Assets<TextureAtlas>>,
     mut textures: ResMut<Assets<Image>>,
+    mut animations: ResMut<Assets<Animation>>,
 ) {
-    let texture_atlas= spawn(
-        "loading".to_string(),
-        &sprite_handles,
-        &mut texture_atlases,
-        &mut textures,
-    );
+    sprite_handles.build_sheet(SpriteType::Heart, &sprites, &mut texture_atlases, &mut textures, &mut animations);
+    // the bar shows progress rather than playing the animation
+    let (texture_atlas, _, _, _) = sprite_handles.sheet(SpriteType::Heart);
     let n = 10;
     let interval = 100.0 / n as f32;
     for i in 0..n {
@@ -73,26 +89,39 @@ fn setup_loading_bar(
     }
 }
 
-fn load_textures(mut sprite_handles: ResMut<SpriteHandles>, asset_server: Res<AssetServer>) {
-    let mut load = |name: SpriteType| {
-        let handles = load_sprites(name, &asset_server);
-        sprite_handles.handles.insert(name.to_string(), handles);
-    };
-    for (key, _) in SPRITES.iter() {
-        use std::str::FromStr;
-        let s = key.to_string();
-        load(SpriteType::from_str(&s).unwrap());
+fn load_textures(
+    mut sprite_handles: ResMut<SpriteHandles>,
+    sprites: Res<SpriteManifest>,
+    asset_server: Res<AssetServer>,
+) {
+    for sprite in SpriteType::ALL {
+        let handles = load_sprites(sprite, &sprites, &asset_server);
+        sprite_handles.handles.insert(sprite, handles);
     }
 }
 
+fn load_levels(mut level_handles: ResMut<LevelHandles>, asset_server: Res<AssetServer>) {
+    level_handles.load(&asset_server);
+}
+
+fn load_physics_config(mut physics_config_handle: ResMut<PhysicsConfigHandle>, asset_server: Res<AssetServer>) {
+    physics_config_handle.load(&asset_server);
+}
+
+#[allow(clippy::too_many_arguments)]
 fn check_textures(
     mut state: ResMut<State<AppState>>,
     sprite_handles: ResMut<SpriteHandles>,
+    sprites: Res<SpriteManifest>,
+    level_handles: Res<LevelHandles>,
+    physics_config_handle: Res<PhysicsConfigHandle>,
     asset_server: Res<AssetServer>,
     texture_atlases: Res<Assets<TextureAtlas>>,
     mut query: Query<(&LoadingBar, &mut TextureAtlasSprite, &Handle<TextureAtlas>)>,
 ) {
-    let ids = sprite_handles.id_list();
+    let ids = sprite_handles.id_list()
+        .chain(level_handles.id_list())
+        .chain(std::iter::once(physics_config_handle.id()));
     let mut total = 0;
     let mut loaded = 0;
     for id in ids {
@@ -104,18 +133,30 @@ fn check_textures(
         }
     }
     if loaded == total {
-        state.set(AppState::Game).unwrap();
+        state.set(AppState::MainMenu).unwrap();
         return;
     }
     let percent = (100*loaded) as f32 / total as f32;
     for (loading_bar, mut sprite, texture_atlas_handle) in query.iter_mut() {
         let image = loading_bar.get_image(percent);
-        let handle = asset_server.get_handle(image);
+        let handle = asset_server.get_handle(sprites.path(SpriteType::Heart, image));
         let texture_atlas = texture_atlases.get(texture_atlas_handle).unwrap();
         sprite.index = texture_atlas.get_texture_index(&handle).unwrap();
     }
 }
 
+fn build_sprite_sheets(
+    sprites: Res<SpriteManifest>,
+    mut sprite_handles: ResMut<SpriteHandles>,
+    mut texture_atlases: ResMut<Assets<TextureAtlas>>,
+    mut textures: ResMut<Assets<Image>>,
+    mut animations: ResMut<Assets<Animation>>,
+) {
+    for sprite in SpriteType::ALL {
+        sprite_handles.build_sheet(sprite, &sprites, &mut texture_atlases, &mut textures, &mut animations);
+    }
+}
+
 fn remove_loading_bar(
     mut commands: Commands,
     query: Query<(Entity, &LoadingBar)>,
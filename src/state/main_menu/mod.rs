@@ -0,0 +1,253 @@
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+use crate::camera::reset_camera;
+use crate::controls::{Action, ActionState, Bindings};
+use crate::menu::{spawn_button, ButtonPressed, MenuButton, MenuSystem};
+use crate::save::SaveData;
+use crate::settings::{spawn_bindings, Rebinding};
+use crate::sprite::{SpriteHandles, SpriteType, SpriteTypeStates};
+use crate::state::{AppState, LevelHandles};
+
+pub struct MainMenu;
+
+#[derive(Component)]
+struct MainMenuEntity;
+
+// the UI of the current page, everything but the background
+#[derive(Component)]
+struct MainMenuPage;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum MainMenuButton {
+    NewGame,
+    Continue,
+    LevelSelect,
+    Level(usize),
+    Settings,
+    Quit,
+    Back,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Page {
+    Main,
+    LevelSelect,
+    Settings,
+}
+
+impl Plugin for MainMenu {
+    fn build(&self, app: &mut App) {
+        app
+            .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(reset_camera))
+            .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(spawn_background))
+            .add_system_set(SystemSet::on_enter(AppState::MainMenu).with_system(show_menu))
+            .add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(back.before(MenuSystem)))
+            .add_system_set(SystemSet::on_update(AppState::MainMenu).with_system(menu_button.after(MenuSystem)))
+            .add_system_set(SystemSet::on_exit(AppState::MainMenu).with_system(cleanup));
+    }
+}
+
+fn spawn_background(
+    mut commands: Commands,
+    sprite_handles: Res<SpriteHandles>,
+) {
+    let tile_size = 24.0;
+    let layer = 0.0;
+    for i in -20..21 {
+        for j in -15..16 {
+            let hill_height = -2;
+            let image = if j < hill_height { SpriteTypeStates::Full } else if j == hill_height { SpriteTypeStates::Half } else { SpriteTypeStates::Empty };
+            let (texture_atlas, sprite, pivot) = sprite_handles.frame(SpriteType::GreenBG, image);
+            commands
+                .spawn_bundle(SpriteSheetBundle {
+                    texture_atlas,
+                    sprite,
+                    transform: Transform::from_translation(Vec3::new(i as f32*tile_size, j as f32*tile_size, layer)),
+                    ..Default::default()
+                })
+                .insert(pivot)
+                .insert(MainMenuEntity);
+        }
+    }
+}
+
+fn show_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    bindings: Res<Bindings>,
+    level_handles: Res<LevelHandles>,
+    save: Res<SaveData>,
+) {
+    spawn_page(&mut commands, &asset_server, &bindings, &level_handles, &save, Page::Main);
+}
+
+fn spawn_page(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    bindings: &Bindings,
+    level_handles: &LevelHandles,
+    save: &SaveData,
+    page: Page,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("kenney-fonts/Fonts/Kenney Pixel.ttf"),
+        font_size: 32.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    let title = match page {
+        Page::Main => "Bevy Starter",
+        Page::LevelSelect => "Levels",
+        Page::Settings => "Controls",
+    };
+    commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(MainMenuEntity)
+        .insert(MainMenuPage)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    title,
+                    TextStyle {
+                        font: asset_server.load("kenney-fonts/Fonts/Kenney Blocks.ttf"),
+                        font_size: 64.0,
+                        color: Color::WHITE,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+            let mut buttons = Vec::new();
+            let mut first_index = 0;
+            match page {
+                Page::Main => {
+                    buttons.push((MainMenuButton::NewGame, "New Game".to_string()));
+                    // there is only something to continue after the first level
+                    if save.current_level > 0 {
+                        buttons.push((MainMenuButton::Continue, "Continue".to_string()));
+                    }
+                    buttons.push((MainMenuButton::LevelSelect, "Level Select".to_string()));
+                    buttons.push((MainMenuButton::Settings, "Settings".to_string()));
+                    buttons.push((MainMenuButton::Quit, "Quit".to_string()));
+                },
+                Page::LevelSelect => {
+                    for level in (0..level_handles.handles.len()).filter(|&level| save.is_unlocked(level)) {
+                        let text = match save.best_times.get(&level) {
+                            Some(time) => format!("Level {} - {:.2}s", level + 1, time),
+                            None => format!("Level {}", level + 1),
+                        };
+                        buttons.push((MainMenuButton::Level(level), text));
+                    }
+                    buttons.push((MainMenuButton::Back, "Back".to_string()));
+                },
+                Page::Settings => {
+                    first_index = spawn_bindings(parent, bindings, &text_style);
+                    buttons.push((MainMenuButton::Back, "Back".to_string()));
+                },
+            }
+            for (index, (button, text)) in buttons.into_iter().enumerate() {
+                spawn_button(parent, &text, text_style.clone())
+                    .insert(MenuButton(first_index + index))
+                    .insert(button);
+            }
+        });
+}
+
+fn change_page(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    bindings: &Bindings,
+    level_handles: &LevelHandles,
+    save: &SaveData,
+    page_query: &Query<Entity, With<MainMenuPage>>,
+    page: Page,
+) {
+    for id in page_query.iter() {
+        commands.entity(id).despawn_recursive();
+    }
+    spawn_page(commands, asset_server, bindings, level_handles, save, page);
+}
+
+// Pause goes back to the first page
+#[allow(clippy::too_many_arguments)]
+fn back(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    bindings: Res<Bindings>,
+    level_handles: Res<LevelHandles>,
+    save: Res<SaveData>,
+    mut actions: ResMut<ActionState>,
+    rebinding: Res<Rebinding>,
+    page_query: Query<Entity, With<MainMenuPage>>,
+) {
+    if rebinding.is_active() || !actions.any_just_pressed(Action::Pause) {
+        return;
+    }
+    change_page(&mut commands, &asset_server, &bindings, &level_handles, &save, &page_query, Page::Main);
+    actions.reset(Action::Pause);
+}
+
+#[allow(clippy::too_many_arguments)]
+fn menu_button(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    bindings: Res<Bindings>,
+    mut level_handles: ResMut<LevelHandles>,
+    save: Res<SaveData>,
+    mut state: ResMut<State<AppState>>,
+    mut events: EventReader<ButtonPressed>,
+    mut exit: EventWriter<AppExit>,
+    button_query: Query<&MainMenuButton>,
+    page_query: Query<Entity, With<MainMenuPage>>,
+) {
+    // only the first press counts, the page it was on might be gone already
+    let button = match events.iter().find_map(|event| button_query.get(event.0).ok()) {
+        Some(button) => *button,
+        None => return,
+    };
+    let page = match button {
+        MainMenuButton::NewGame => {
+            level_handles.select(0);
+            state.set(AppState::Game).unwrap();
+            return;
+        },
+        MainMenuButton::Continue => {
+            level_handles.select(save.current_level);
+            state.set(AppState::Game).unwrap();
+            return;
+        },
+        MainMenuButton::Level(level) => {
+            level_handles.select(level);
+            state.set(AppState::Game).unwrap();
+            return;
+        },
+        MainMenuButton::Quit => {
+            exit.send(AppExit);
+            return;
+        },
+        MainMenuButton::LevelSelect => Page::LevelSelect,
+        MainMenuButton::Settings => Page::Settings,
+        MainMenuButton::Back => Page::Main,
+    };
+    change_page(&mut commands, &asset_server, &bindings, &level_handles, &save, &page_query, page);
+}
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<MainMenuEntity>>,
+) {
+    for id in query.iter() {
+        commands.entity(id).despawn_recursive();
+    }
+}
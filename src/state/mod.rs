@@ -2,16 +2,22 @@
 pub enum AppState {
     PreLoad,
     Loading,
+    MainMenu,
     Game,
     GameOver,
+    LevelComplete,
     Pause,
 }
 
 mod game;
-pub use game::Game;
+pub use game::{CollisionGrid, Game, Hitbox, LevelEndSystem, LevelEntity, LevelHandles, LevelTime, Map, PhysicsConfigHandle, Score, Tile};
 mod loading;
 pub use loading::Loading;
+mod main_menu;
+pub use main_menu::MainMenu;
 mod game_over;
 pub use game_over::GameOver;
+mod level_complete;
+pub use level_complete::LevelComplete;
 mod pause;
 pub use pause::Pause;
@@ -1,31 +1,190 @@
 use bevy::prelude::*;
 
 use crate::AppState;
-use crate::controls::Controls;
+use crate::controls::{Action, ActionState, Bindings};
+use crate::menu::{spawn_button, ButtonPressed, MenuButton, MenuSystem};
+use crate::settings::{spawn_bindings, Rebinding};
+use crate::state::{LevelEndSystem, LevelEntity};
 
 pub struct Pause;
 
+#[derive(Component)]
+struct PauseEntity;
+
+#[derive(Component, Clone, Copy, PartialEq)]
+enum PauseButton {
+    Resume,
+    Restart,
+    Settings,
+    Quit,
+    Back,
+}
+
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Page {
+    #[default]
+    Main,
+    Settings,
+}
+
 impl Plugin for Pause {
     fn build(&self, app: &mut App) {
         app
-            .add_system_set(SystemSet::on_update(AppState::Game).with_system(pause))
-            .add_system_set(SystemSet::on_update(AppState::Pause).with_system(pause));
+            .init_resource::<Page>()
+            .add_system_set(SystemSet::on_update(AppState::Game).with_system(pause.after(LevelEndSystem)))
+            .add_system_set(SystemSet::on_update(AppState::Pause).with_system(pause.before(MenuSystem)))
+            .add_system_set(SystemSet::on_enter(AppState::Pause).with_system(show_menu))
+            .add_system_set(SystemSet::on_update(AppState::Pause).with_system(pause_button.after(MenuSystem)))
+            .add_system_set(SystemSet::on_exit(AppState::Pause).with_system(cleanup));
     }
 }
 
+// Pause opens the menu from the game, and goes back a page or resumes from the menu
+#[allow(clippy::too_many_arguments)]
 fn pause(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    bindings: Res<Bindings>,
     mut state: ResMut<State<AppState>>,
-    mut input: ResMut<Input<KeyCode>>,
-    query: Query<&Controls>,
+    mut actions: ResMut<ActionState>,
+    mut page: ResMut<Page>,
+    rebinding: Res<Rebinding>,
+    query: Query<Entity, With<PauseEntity>>,
+) {
+    if rebinding.is_active() || !actions.any_just_pressed(Action::Pause) {
+        return;
+    }
+    match (state.current(), *page) {
+        // the level ending this frame takes precedence over pausing
+        (AppState::Game, _) => if state.push(AppState::Pause).is_err() {
+            return;
+        },
+        (AppState::Pause, Page::Main) => state.pop().unwrap(),
+        (AppState::Pause, Page::Settings) => {
+            despawn_page(&mut commands, &query);
+            *page = Page::Main;
+            spawn_page(&mut commands, &asset_server, &bindings, *page);
+        },
+        _ => {},
+    }
+    actions.reset(Action::Pause);
+}
+
+fn show_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    bindings: Res<Bindings>,
+    mut page: ResMut<Page>,
 ) {
-    for controls in query.iter() {
-        if input.just_pressed(controls.pause) {
-            match state.current() {
-                AppState::Game => state.push(AppState::Pause).unwrap(),
-                AppState::Pause => state.pop().unwrap(),
-                _ => unimplemented!(),
+    *page = Page::Main;
+    spawn_page(&mut commands, &asset_server, &bindings, *page);
+}
+
+fn despawn_page(commands: &mut Commands, query: &Query<Entity, With<PauseEntity>>) {
+    for id in query.iter() {
+        commands.entity(id).despawn_recursive();
+    }
+}
+
+fn spawn_page(commands: &mut Commands, asset_server: &AssetServer, bindings: &Bindings, page: Page) {
+    let text_style = TextStyle {
+        font: asset_server.load("kenney-fonts/Fonts/Kenney Pixel.ttf"),
+        font_size: 32.0,
+        color: Color::rgb(0.9, 0.9, 0.9),
+    };
+    let title = match page {
+        Page::Main => "Paused",
+        Page::Settings => "Controls",
+    };
+    commands
+        // covers the whole screen, dimming the world behind the menu
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::ColumnReverse,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..Default::default()
+            },
+            color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+            ..Default::default()
+        })
+        .insert(PauseEntity)
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section(
+                    title,
+                    TextStyle {
+                        font: asset_server.load("kenney-fonts/Fonts/Kenney Blocks.ttf"),
+                        font_size: 64.0,
+                        color: Color::WHITE,
+                    },
+                    Default::default(),
+                ),
+                ..Default::default()
+            });
+            match page {
+                Page::Main => {
+                    let buttons = [
+                        (PauseButton::Resume, "Resume"),
+                        (PauseButton::Restart, "Restart level"),
+                        (PauseButton::Settings, "Settings"),
+                        (PauseButton::Quit, "Quit to title"),
+                    ];
+                    for (index, (button, text)) in buttons.into_iter().enumerate() {
+                        spawn_button(parent, text, text_style.clone())
+                            .insert(MenuButton(index))
+                            .insert(button);
+                    }
+                },
+                Page::Settings => {
+                    let back_index = spawn_bindings(parent, bindings, &text_style);
+                    spawn_button(parent, "Back", text_style.clone())
+                        .insert(MenuButton(back_index))
+                        .insert(PauseButton::Back);
+                },
             }
-            input.reset(controls.pause);
-        }
+        });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn pause_button(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    bindings: Res<Bindings>,
+    mut state: ResMut<State<AppState>>,
+    mut page: ResMut<Page>,
+    mut events: EventReader<ButtonPressed>,
+    button_query: Query<&PauseButton>,
+    page_query: Query<Entity, With<PauseEntity>>,
+    level_query: Query<Entity, With<LevelEntity>>,
+) {
+    // only the first press counts, the page it was on might be gone already
+    let button = match events.iter().find_map(|event| button_query.get(event.0).ok()) {
+        Some(button) => *button,
+        None => return,
+    };
+    match button {
+        PauseButton::Resume => state.pop().unwrap(),
+        PauseButton::Restart | PauseButton::Quit => {
+            for id in level_query.iter() {
+                commands.entity(id).despawn_recursive();
+            }
+            let next_state = if button == PauseButton::Restart { AppState::Game } else { AppState::MainMenu };
+            state.replace(next_state).unwrap();
+        },
+        PauseButton::Settings | PauseButton::Back => {
+            despawn_page(&mut commands, &page_query);
+            *page = if button == PauseButton::Settings { Page::Settings } else { Page::Main };
+            spawn_page(&mut commands, &asset_server, &bindings, *page);
+        },
     }
 }
+
+fn cleanup(
+    mut commands: Commands,
+    query: Query<Entity, With<PauseEntity>>,
+) {
+    despawn_page(&mut commands, &query);
+}